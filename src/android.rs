@@ -36,7 +36,7 @@ pub fn start_android(app: AndroidApp) {
             let mut notedeck =
                 notedeck_chrome::Notedeck::new(&cc.egui_ctx, data_path.clone(), &args);
 
-            let zs_app = ZapStreamApp::new(cc, app.clone());
+            let zs_app = ZapStreamApp::new(cc, app.clone(), data_path.clone());
             notedeck.add_app(zs_app);
 
             Ok(Box::new(notedeck))
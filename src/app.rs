@@ -1,16 +1,24 @@
+use crate::overlay::OverlayConfig;
 use crate::profiles::ProfileLoader;
-use crate::route::{page, RouteAction, RouteServices, RouteType};
-use crate::theme::MARGIN_DEFAULT;
-use crate::widgets::{Header, NostrWidget};
+use crate::route::{page, AppServices, RouteAction, RouteServices, RouteType};
+use crate::services::live_watcher::LiveWatcher;
+use crate::services::notifications::Notifications;
+use crate::settings::BackgroundPlayback;
+use crate::theme::{MARGIN_DEFAULT, NEUTRAL_500, NEUTRAL_800, PRIMARY, ROUNDING_DEFAULT};
+use crate::widgets::{Button, Header, MiniPlayerAction, NostrWidget};
 use eframe::epaint::{FontFamily, Margin};
 use eframe::CreationContext;
-use egui::{Color32, FontData, FontDefinitions, Theme, Ui, Visuals};
+use egui::{
+    vec2, Align2, Area, Color32, Context, FontData, FontDefinitions, Frame, Id, Order, RichText,
+    Theme, Ui, Visuals,
+};
 use enostr::{PoolEvent, RelayEvent, RelayMessage};
 use log::{error, info, warn};
 use nostrdb::{Filter, Transaction};
 use notedeck::AppContext;
 use poll_promise::Promise;
 use std::collections::HashMap;
+use std::path::PathBuf;
 use std::sync::mpsc;
 
 pub struct ZapStreamApp {
@@ -24,18 +32,30 @@ pub struct ZapStreamApp {
     widget: Box<dyn NostrWidget>,
     profiles: ProfileLoader,
     fetch: HashMap<String, Promise<ehttp::Result<ehttp::Response>>>,
+    services: AppServices,
+    live_watcher: LiveWatcher,
+    notifications: Notifications,
+    /// Running in chat overlay mode
+    overlay: Option<OverlayConfig>,
 }
 
 #[cfg(target_os = "android")]
 impl ZapStreamApp {
-    pub fn new(cc: &CreationContext, app: android_activity::AndroidApp) -> Self {
+    pub fn new(
+        cc: &CreationContext,
+        app: android_activity::AndroidApp,
+        data_path: PathBuf,
+    ) -> Self {
         let mut fd = FontDefinitions::default();
         fd.font_data.insert(
             "Outfit".to_string(),
             FontData::from_static(include_bytes!("../assets/Outfit-Light.ttf")),
         );
+        // keep default fonts as fallback for emoji
         fd.families
-            .insert(FontFamily::Proportional, vec!["Outfit".to_string()]);
+            .entry(FontFamily::Proportional)
+            .or_default()
+            .insert(0, "Outfit".to_string());
         cc.egui_ctx.set_fonts(fd);
 
         let (tx, rx) = mpsc::channel();
@@ -45,6 +65,11 @@ impl ZapStreamApp {
             profiles: ProfileLoader::new(),
             routes_tx: tx,
             routes_rx: rx,
+            fetch: HashMap::new(),
+            services: AppServices::new(&data_path),
+            live_watcher: LiveWatcher::new(),
+            notifications: Notifications::new(),
+            overlay: None,
             app,
         }
     }
@@ -52,14 +77,17 @@ impl ZapStreamApp {
 
 #[cfg(not(target_os = "android"))]
 impl ZapStreamApp {
-    pub fn new(cc: &CreationContext) -> Self {
+    pub fn new(cc: &CreationContext, data_path: PathBuf) -> Self {
         let mut fd = FontDefinitions::default();
         fd.font_data.insert(
             "Outfit".to_string(),
             FontData::from_static(include_bytes!("../assets/Outfit-Light.ttf")),
         );
+        // keep default fonts as fallback for emoji
         fd.families
-            .insert(FontFamily::Proportional, vec!["Outfit".to_string()]);
+            .entry(FontFamily::Proportional)
+            .or_default()
+            .insert(0, "Outfit".to_string());
         cc.egui_ctx.set_fonts(fd);
 
         // ffmpeg log redirect
@@ -76,26 +104,50 @@ impl ZapStreamApp {
             routes_tx: tx,
             routes_rx: rx,
             fetch: HashMap::new(),
+            services: AppServices::new(&data_path),
+            live_watcher: LiveWatcher::new(),
+            notifications: Notifications::new(),
+            overlay: None,
         }
     }
 }
 
+impl ZapStreamApp {
+    /// Only show the chat overlay for a single stream
+    pub fn with_overlay(mut self, config: OverlayConfig) -> Self {
+        self.widget = Box::new(page::OverlayPage::new(config.clone()));
+        self.overlay = Some(config);
+        self
+    }
+}
+
 impl notedeck::App for ZapStreamApp {
     fn update(&mut self, ctx: &mut AppContext<'_>, ui: &mut Ui) {
         ctx.accounts.update(ctx.ndb, ctx.pool, ui.ctx());
         while let Some(PoolEvent { event, relay }) = ctx.pool.try_recv() {
-            if let RelayEvent::Message(msg) = (&event).into() {
-                match msg {
-                    RelayMessage::OK(_) => {}
-                    RelayMessage::Eose(_) => {}
-                    RelayMessage::Event(_sub, ev) => {
+            let relay = relay.to_string();
+            match (&event).into() {
+                RelayEvent::Opened => self.services.outbox.retry(ctx.pool, &relay),
+                RelayEvent::Message(msg) => match msg {
+                    RelayMessage::OK(_) => {
+                        if let ewebsock::WsEvent::Message(ewebsock::WsMessage::Text(txt)) = &event {
+                            self.services.outbox.handle_ok(&relay, txt);
+                        }
+                        ui.ctx().request_repaint();
+                    }
+                    RelayMessage::Eose(sub) => {
+                        self.services.relay_results.handle_eose(sub, &relay);
+                    }
+                    RelayMessage::Event(sub, ev) => {
                         if let Err(e) = ctx.ndb.process_event(ev) {
                             error!("Error processing event: {:?}", e);
                         }
+                        self.services.relay_results.handle_event(sub, ev);
                         ui.ctx().request_repaint();
                     }
                     RelayMessage::Notice(m) => warn!("Notice from {}: {}", relay, m),
-                }
+                },
+                _ => {}
             }
         }
 
@@ -103,7 +155,10 @@ impl notedeck::App for ZapStreamApp {
         ui.ctx().set_visuals_of(
             Theme::Dark,
             Visuals {
-                panel_fill: Color32::BLACK,
+                panel_fill: self
+                    .overlay
+                    .as_ref()
+                    .map_or(Color32::BLACK, |o| o.background),
                 override_text_color: Some(Color32::WHITE),
                 ..Default::default()
             },
@@ -113,6 +168,9 @@ impl notedeck::App for ZapStreamApp {
 
         let app_frame = egui::containers::Frame::default().outer_margin(self.frame_margin());
 
+        self.services.downloads.update(ui.ctx());
+        self.services.settings.flush(ui.ctx());
+
         // handle app state changes
         while let Ok(r) = self.routes_rx.try_recv() {
             if let RouteType::Action(a) = r {
@@ -122,8 +180,15 @@ impl notedeck::App for ZapStreamApp {
                     }
                     _ => info!("Not implemented"),
                 }
+            } else if self.overlay.is_some() {
+                // no navigation in overlay mode
+                continue;
             } else {
                 self.current = r;
+                match &self.current {
+                    RouteType::EventPage { link, .. } => self.services.players.attach(link),
+                    _ => self.services.players.detach(),
+                }
                 match &self.current {
                     RouteType::HomePage => {
                         self.widget = Box::new(page::HomePage::new());
@@ -134,6 +199,21 @@ impl notedeck::App for ZapStreamApp {
                     RouteType::LoginPage => {
                         self.widget = Box::new(page::LoginPage::new());
                     }
+                    RouteType::LibraryPage => {
+                        self.widget = Box::new(page::LibraryPage::new());
+                    }
+                    RouteType::SearchPage => {
+                        self.widget = Box::new(page::SearchPage::new());
+                    }
+                    RouteType::SettingsPage => {
+                        self.widget = Box::new(page::SettingsPage::new());
+                    }
+                    RouteType::AgendaPage => {
+                        self.widget = Box::new(page::AgendaPage::new());
+                    }
+                    RouteType::TagPage { tag } => {
+                        self.widget = Box::new(page::TagPage::new(tag.clone()));
+                    }
                     RouteType::ProfilePage { link } => {
                         self.widget = Box::new(page::ProfilePage::new(
                             link.id.as_bytes().try_into().unwrap(),
@@ -148,6 +228,13 @@ impl notedeck::App for ZapStreamApp {
             .frame(app_frame)
             .show(ui.ctx(), |ui| {
                 let tx = Transaction::new(ctx.ndb).expect("transaction");
+                if let Err(e) =
+                    self.services
+                        .reminders
+                        .update(ctx.ndb, &tx, ctx.pool, &mut self.notifications)
+                {
+                    error!("Failed to update reminders: {}", e);
+                }
                 // display app
                 ui.vertical(|ui| {
                     let mut svc = RouteServices::new(
@@ -156,8 +243,18 @@ impl notedeck::App for ZapStreamApp {
                         ctx,
                         self.routes_tx.clone(),
                         &mut self.fetch,
+                        &mut self.services,
                     );
-                    Header::new().render(ui, &mut svc, &tx);
+                    let fullscreen = ui.ctx().input(|i| i.viewport().fullscreen.unwrap_or(false));
+                    if self.overlay.is_none() && !fullscreen {
+                        Header::new().render(ui, &mut svc, &tx);
+                    }
+                    if self.overlay.is_none() {
+                        if let Err(e) = self.live_watcher.update(&mut svc, &mut self.notifications)
+                        {
+                            error!("Failed to update live watcher: {}", e);
+                        }
+                    }
                     if let Err(e) = self.widget.update(&mut svc) {
                         error!("{}", e);
                     }
@@ -166,6 +263,36 @@ impl notedeck::App for ZapStreamApp {
                 .response
             });
 
+        match self
+            .players
+            .show(ui.ctx(), self.services.settings.background_playback)
+        {
+            Some(MiniPlayerAction::Expand) => {
+                if let Some(link) = self.services.players.link() {
+                    self.routes_tx
+                        .send(RouteType::EventPage {
+                            link: link.clone(),
+                            event: None,
+                        })
+                        .expect("route send failed");
+                }
+            }
+            Some(MiniPlayerAction::ToggleAudioOnly) => {
+                self.services.settings.background_playback =
+                    match self.services.settings.background_playback {
+                        BackgroundPlayback::MiniPlayer => BackgroundPlayback::AudioOnly,
+                        BackgroundPlayback::AudioOnly => BackgroundPlayback::MiniPlayer,
+                    };
+                self.services.settings.save();
+            }
+            Some(MiniPlayerAction::Close) => self.services.players.stop(),
+            None => {}
+        }
+
+        if self.overlay.is_none() {
+            self.show_alerts(ui.ctx());
+        }
+
         let profiles = self.profiles.next();
         if !profiles.is_empty() {
             info!("Profiles: {:?}", profiles);
@@ -177,6 +304,52 @@ impl notedeck::App for ZapStreamApp {
     }
 }
 
+impl ZapStreamApp {
+    /// Notifications in the top right corner until dismissed
+    fn show_alerts(&mut self, ctx: &Context) {
+        if self.notifications.alerts().is_empty() {
+            return;
+        }
+        let mut dismiss = None;
+        Area::new(Id::new("alerts"))
+            .anchor(Align2::RIGHT_TOP, vec2(-16., 56.))
+            .order(Order::Foreground)
+            .show(ctx, |ui| {
+                ui.set_max_width(300.);
+                for (idx, a) in self.notifications.alerts().iter().enumerate() {
+                    Frame::none()
+                        .fill(NEUTRAL_800)
+                        .rounding(ROUNDING_DEFAULT)
+                        .inner_margin(MARGIN_DEFAULT)
+                        .show(ui, |ui| {
+                            ui.label(&a.title);
+                            ui.label(RichText::new(&a.body).color(NEUTRAL_500));
+                            ui.horizontal(|ui| {
+                                if let Some(link) = a.link.clone() {
+                                    if Button::new()
+                                        .with_color(PRIMARY)
+                                        .text(ui, "Watch")
+                                        .clicked()
+                                    {
+                                        self.routes_tx
+                                            .send(RouteType::EventPage { link, event: None })
+                                            .expect("route send failed");
+                                        dismiss = Some(idx);
+                                    }
+                                }
+                                if Button::new().text(ui, "Dismiss").clicked() {
+                                    dismiss = Some(idx);
+                                }
+                            });
+                        });
+                }
+            });
+        if let Some(idx) = dismiss {
+            self.notifications.dismiss(idx);
+        }
+    }
+}
+
 #[cfg(not(target_os = "android"))]
 impl ZapStreamApp {
     fn frame_margin(&self) -> Margin {
@@ -4,13 +4,23 @@ use eframe::Renderer;
 use egui::{Vec2, ViewportBuilder};
 use log::{error, info};
 use zap_stream_app::app::ZapStreamApp;
+use zap_stream_app::overlay::OverlayConfig;
 
 #[tokio::main]
 async fn main() -> Result<()> {
     pretty_env_logger::init();
 
+    let args: Vec<String> = std::env::args().collect();
+    let overlay = OverlayConfig::from_args(&args)?;
+
     let mut options = eframe::NativeOptions::default();
-    options.viewport = ViewportBuilder::default().with_inner_size(Vec2::new(1300., 900.));
+    options.viewport = if overlay.is_some() {
+        ViewportBuilder::default()
+            .with_inner_size(Vec2::new(500., 800.))
+            .with_transparent(true)
+    } else {
+        ViewportBuilder::default().with_inner_size(Vec2::new(1300., 900.))
+    };
     options.renderer = Renderer::Glow;
 
     let data_path = ProjectDirs::from("stream", "zap", "zap_stream_app")
@@ -23,11 +33,13 @@ async fn main() -> Result<()> {
         "zap.stream",
         options,
         Box::new(move |cc| {
-            let args: Vec<String> = std::env::args().collect();
             let mut notedeck =
                 notedeck_chrome::Notedeck::new(&cc.egui_ctx, data_path.clone(), &args);
 
-            let app = ZapStreamApp::new(cc);
+            let mut app = ZapStreamApp::new(cc, data_path.clone());
+            if let Some(overlay) = overlay {
+                app = app.with_overlay(overlay);
+            }
             notedeck.add_app(app);
 
             Ok(Box::new(notedeck))
@@ -0,0 +1,163 @@
+use crate::note_ref::NoteRef;
+use crate::note_util::TagIterBorrow;
+use crate::route::RouteServices;
+use crate::sub::SubRef;
+use crate::widgets::sub_or_poll;
+use anyhow::anyhow;
+use nostr::prelude::hex;
+use nostr::{EventBuilder, Kind, Tag};
+use nostrdb::{Filter, Ndb, NdbStrVariant, Note, Transaction};
+use std::collections::HashSet;
+use std::time::{Duration, Instant};
+
+/// Time to wait for the contact list before treating it as empty
+const LOAD_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Contact list (kind 3) of the logged in account, or of a fixed pubkey
+pub struct Contacts {
+    pubkey: Option<[u8; 32]>,
+    /// Track the logged in account
+    account: bool,
+    events: HashSet<NoteRef>,
+    sub: Option<SubRef>,
+    subscribed: Option<Instant>,
+}
+
+impl Contacts {
+    pub fn new() -> Self {
+        Self {
+            pubkey: None,
+            account: true,
+            events: HashSet::new(),
+            sub: None,
+            subscribed: None,
+        }
+    }
+
+    /// Contact list of another pubkey
+    pub fn of(pubkey: [u8; 32]) -> Self {
+        Self {
+            pubkey: Some(pubkey),
+            account: false,
+            ..Self::new()
+        }
+    }
+
+    fn latest<'a>(&self, ndb: &Ndb, tx: &'a Transaction) -> Option<Note<'a>> {
+        let latest = self.events.iter().max_by_key(|e| e.created_at)?;
+        ndb.get_note_by_key(tx, latest.key).ok()
+    }
+
+    /// Followed pubkeys, empty when logged out or not loaded
+    pub fn follows(&self, ndb: &Ndb, tx: &Transaction) -> HashSet<[u8; 32]> {
+        let note = match self.latest(ndb, tx) {
+            Some(n) => n,
+            None => return HashSet::new(),
+        };
+        note.tags()
+            .iter()
+            .filter_map(|t| {
+                if t.count() < 2 || t.get(0)?.variant().str() != Some("p") {
+                    return None;
+                }
+                match t.get(1)?.variant() {
+                    NdbStrVariant::Id(i) => Some(*i),
+                    NdbStrVariant::Str(s) => hex::decode(s).ok()?.try_into().ok(),
+                }
+            })
+            .collect()
+    }
+
+    /// A contact list event was found
+    pub fn found(&self) -> bool {
+        !self.events.is_empty()
+    }
+
+    /// Contact list was found, or didn't show up in time
+    pub fn loaded(&self) -> bool {
+        !self.events.is_empty()
+            || self
+                .subscribed
+                .map_or(false, |t| t.elapsed() > LOAD_TIMEOUT)
+    }
+
+    /// Publish the contact list with `pubkey` added or removed, keeping all other tags.
+    ///
+    /// Without a contact list event a new list is only started with `create`,
+    /// the list may just not have loaded from the relays.
+    pub fn set_follow(
+        &self,
+        services: &mut RouteServices<'_, '_>,
+        pubkey: &[u8; 32],
+        follow: bool,
+        create: bool,
+    ) -> anyhow::Result<()> {
+        if !self.loaded() {
+            anyhow::bail!("Contact list is not loaded yet");
+        }
+        if !self.found() && !create {
+            anyhow::bail!("No contact list found");
+        }
+        let keys = services
+            .current_account_keys()
+            .ok_or(anyhow!("Login to follow"))?;
+        let target = hex::encode(pubkey);
+        let latest = self.latest(services.ctx.ndb, services.tx);
+        let mut tags = vec![];
+        if let Some(note) = &latest {
+            let note_tags = note.tags();
+            for t in note_tags.iter() {
+                let values: Vec<String> = TagIterBorrow::new(&t)
+                    .map(|v| match v.variant() {
+                        NdbStrVariant::Id(i) => hex::encode(i),
+                        NdbStrVariant::Str(s) => s.to_string(),
+                    })
+                    .collect();
+                if values.first().map(|v| v.as_str()) == Some("p") && values.get(1) == Some(&target)
+                {
+                    continue;
+                }
+                tags.push(Tag::parse(values)?);
+            }
+        }
+        if follow {
+            tags.push(Tag::parse(vec!["p".to_string(), target])?);
+        }
+        let content = latest.as_ref().map_or("", |n| n.content());
+        let ev = EventBuilder::new(Kind::ContactList, content)
+            .tags(tags)
+            .sign_with_keys(&keys)?;
+        services.broadcast_event(ev);
+        Ok(())
+    }
+
+    pub fn update(&mut self, services: &mut RouteServices<'_, '_>) -> anyhow::Result<()> {
+        if self.account {
+            let pubkey = services
+                .ctx
+                .accounts
+                .get_selected_account()
+                .map(|a| *a.pubkey.bytes());
+            if pubkey != self.pubkey {
+                self.pubkey = pubkey;
+                self.events.clear();
+                self.sub = None;
+            }
+        }
+        let pubkey = match self.pubkey {
+            Some(p) => p,
+            None => return Ok(()),
+        };
+        if self.sub.is_none() {
+            self.subscribed = Some(Instant::now());
+        }
+        sub_or_poll(
+            services.ctx.ndb,
+            services.tx,
+            services.ctx.pool,
+            &mut self.events,
+            &mut self.sub,
+            vec![Filter::new().kinds([3]).authors(&[pubkey]).limit(1).build()],
+        )
+    }
+}
@@ -0,0 +1,510 @@
+use anyhow::{anyhow, bail, Result};
+use egui::Context;
+use itertools::Itertools;
+use m3u8_rs::{AlternativeMediaType, Playlist, VariantStream};
+use nostr::Url;
+use poll_promise::Promise;
+use std::collections::VecDeque;
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+/// Max number of throughput samples kept
+const ABR_SAMPLES: usize = 5;
+
+/// Only use this fraction of the measured throughput when picking a variant
+const ABR_SAFETY: f64 = 0.8;
+
+/// Max length of the live DVR window
+const DVR_WINDOW_SECS: f32 = 2.0 * 60.0 * 60.0;
+
+/// Number of segments from the end where ffmpeg starts playing a live playlist
+const LIVE_START_SEGMENTS: usize = 3;
+
+/// Bitrate used to pick the first variant before any throughput is known
+const ABR_DEFAULT_BPS: f64 = 2_500_000.0;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct HlsVariant {
+    pub url: String,
+    pub bandwidth: u64,
+    pub resolution: Option<(u64, u64)>,
+}
+
+impl HlsVariant {
+    /// Display name for the quality menu
+    pub fn label(&self) -> String {
+        match self.resolution {
+            Some((_, h)) => format!("{}p", h),
+            None => format!("{} kbps", self.bandwidth / 1000),
+        }
+    }
+}
+
+/// Resolve a playlist uri against the playlist url or local file path
+pub fn resolve_url(base: &str, uri: &str) -> Result<String> {
+    if let Ok(u) = Url::parse(uri) {
+        return Ok(u.to_string());
+    }
+    match Url::parse(base) {
+        Ok(b) => Ok(b.join(uri)?.to_string()),
+        Err(_) => Ok(Path::new(base)
+            .parent()
+            .ok_or(anyhow!("Invalid playlist path"))?
+            .join(uri)
+            .to_string_lossy()
+            .to_string()),
+    }
+}
+
+/// Load a playlist from a url or local file
+pub fn load_playlist(ctx: &Context, url: &str) -> Promise<ehttp::Result<ehttp::Response>> {
+    let ctx = ctx.clone();
+    if url.starts_with("http") {
+        let (sender, promise) = Promise::new();
+        ehttp::fetch(ehttp::Request::get(url), move |rsp| {
+            sender.send(rsp);
+            ctx.request_repaint();
+        });
+        promise
+    } else {
+        let path = url.trim_start_matches("file://").to_string();
+        Promise::spawn_thread("load_playlist", move || {
+            let bytes = std::fs::read(&path).map_err(|e| e.to_string())?;
+            ctx.request_repaint();
+            Ok(ehttp::Response {
+                url: path,
+                ok: true,
+                status: 200,
+                status_text: "OK".to_string(),
+                headers: Default::default(),
+                bytes,
+            })
+        })
+    }
+}
+
+/// Variant without a video codec, only known when CODECS is set
+fn is_audio_only(v: &VariantStream) -> bool {
+    v.codecs.as_ref().map_or(false, |c| {
+        !c.split(',').any(|c| {
+            let c = c.trim();
+            ["avc", "hvc", "hev", "vp0", "vp9", "av01", "mp4v"]
+                .iter()
+                .any(|v| c.starts_with(v))
+        })
+    })
+}
+
+/// Parse the variants of a master playlist, sorted highest bandwidth first.
+///
+/// Returns an empty list when this is a media playlist.
+pub fn parse_master(url: &str, data: &[u8]) -> Result<Vec<HlsVariant>> {
+    let playlist =
+        m3u8_rs::parse_playlist_res(data).map_err(|e| anyhow!("Invalid playlist: {:?}", e))?;
+    let mut variants = match playlist {
+        Playlist::MasterPlaylist(m) => m
+            .variants
+            .into_iter()
+            .filter(|v| !v.is_i_frame && !is_audio_only(v))
+            .filter_map(|v| {
+                Some(HlsVariant {
+                    url: resolve_url(url, &v.uri).ok()?,
+                    bandwidth: v.bandwidth,
+                    resolution: v.resolution.map(|r| (r.width, r.height)),
+                })
+            })
+            .collect(),
+        Playlist::MediaPlaylist(_) => vec![],
+    };
+    variants.sort_by(|a, b| b.bandwidth.cmp(&a.bandwidth));
+    Ok(variants)
+}
+
+/// Url of an audio only rendition in a master playlist.
+///
+/// Prefers an audio only variant, then the default audio group media.
+pub fn parse_audio(url: &str, data: &[u8]) -> Option<String> {
+    let master = match m3u8_rs::parse_playlist_res(data) {
+        Ok(Playlist::MasterPlaylist(m)) => m,
+        _ => return None,
+    };
+    let uri = master
+        .variants
+        .iter()
+        .filter(|v| !v.is_i_frame && is_audio_only(v))
+        .min_by_key(|v| v.bandwidth)
+        .map(|v| v.uri.clone())
+        .or_else(|| {
+            master
+                .alternatives
+                .iter()
+                .filter(|a| a.media_type == AlternativeMediaType::Audio)
+                .sorted_by_key(|a| !a.default)
+                .find_map(|a| a.uri.clone())
+        })?;
+    resolve_url(url, &uri).ok()
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct HlsSegment {
+    pub sequence: u64,
+    pub url: String,
+    pub duration: f32,
+}
+
+/// Write a media playlist for a list of segments, gaps in the sequence are marked as discontinuities.
+///
+/// fMP4 segments need the `init` segment of their stream.
+pub fn write_media_playlist(segments: &[HlsSegment], init: Option<&str>, ended: bool) -> String {
+    let target = segments
+        .iter()
+        .map(|s| s.duration.ceil() as u64)
+        .max()
+        .unwrap_or(1);
+    let mut out = format!(
+        "#EXTM3U\n#EXT-X-VERSION:{}\n#EXT-X-TARGETDURATION:{}\n#EXT-X-MEDIA-SEQUENCE:{}\n",
+        if init.is_some() { 6 } else { 3 },
+        target,
+        segments.first().map_or(0, |s| s.sequence)
+    );
+    if let Some(init) = init {
+        out.push_str(&format!("#EXT-X-MAP:URI=\"{}\"\n", init));
+    }
+    for (i, s) in segments.iter().enumerate() {
+        // missing segments break the timeline
+        if i > 0 && segments[i - 1].sequence + 1 != s.sequence {
+            out.push_str("#EXT-X-DISCONTINUITY\n");
+        }
+        out.push_str(&format!("#EXTINF:{:.3},\n{}\n", s.duration, s.url));
+    }
+    if ended {
+        out.push_str("#EXT-X-ENDLIST\n");
+    }
+    out
+}
+
+/// Tracks the segments of a live media playlist to provide a DVR window
+pub struct LiveTracker {
+    url: String,
+    segments: VecDeque<HlsSegment>,
+    target_duration: f32,
+    ended: bool,
+    /// When the newest segment was first seen
+    edge_seen: Option<Instant>,
+    /// Duration of the segments which dropped out of the window
+    dropped: f32,
+    /// `EXT-X-MAP` init segment of fMP4 streams
+    init: Option<String>,
+}
+
+impl LiveTracker {
+    pub fn new(url: &str) -> Self {
+        Self {
+            url: url.to_string(),
+            segments: VecDeque::new(),
+            target_duration: 2.0,
+            ended: false,
+            edge_seen: None,
+            dropped: 0.0,
+            init: None,
+        }
+    }
+
+    pub fn url(&self) -> &str {
+        &self.url
+    }
+
+    /// Playlist has an ENDLIST tag, there is no live edge
+    pub fn ended(&self) -> bool {
+        self.ended
+    }
+
+    /// Init segment the media segments depend on
+    pub fn init(&self) -> Option<&str> {
+        self.init.as_deref()
+    }
+
+    /// Merge a reload of the media playlist
+    pub fn update(&mut self, data: &[u8]) -> Result<()> {
+        let playlist = match m3u8_rs::parse_playlist_res(data)
+            .map_err(|e| anyhow!("Invalid playlist: {:?}", e))?
+        {
+            Playlist::MediaPlaylist(m) => m,
+            Playlist::MasterPlaylist(_) => bail!("Expected media playlist"),
+        };
+        self.target_duration = playlist.target_duration as f32;
+        self.ended = playlist.end_list;
+
+        let last_seq = self.segments.back().map(|s| s.sequence);
+        for (i, seg) in playlist.segments.iter().enumerate() {
+            if let Some(map) = &seg.map {
+                self.init = Some(resolve_url(&self.url, &map.uri)?);
+            }
+            let sequence = playlist.media_sequence + i as u64;
+            if last_seq.map_or(true, |l| sequence > l) {
+                self.segments.push_back(HlsSegment {
+                    sequence,
+                    url: resolve_url(&self.url, &seg.uri)?,
+                    duration: seg.duration,
+                });
+                self.edge_seen = Some(Instant::now());
+            }
+        }
+        while !self.ended && self.window() > DVR_WINDOW_SECS {
+            if let Some(s) = self.segments.pop_front() {
+                self.dropped += s.duration;
+            }
+        }
+        Ok(())
+    }
+
+    /// Length of the DVR window in seconds
+    pub fn window(&self) -> f32 {
+        self.segments.iter().map(|s| s.duration).sum()
+    }
+
+    /// Position of the live edge since the first segment was seen
+    pub fn edge_time(&self) -> f32 {
+        self.dropped + self.window()
+    }
+
+    /// Segments overlapping the time range, in the timeline of [Self::edge_time]
+    pub fn segments_between(&self, start: f32, end: f32) -> Vec<HlsSegment> {
+        let mut t = self.dropped;
+        self.segments
+            .iter()
+            .filter(|s| {
+                let seg_start = t;
+                t += s.duration;
+                seg_start < end && t > start
+            })
+            .cloned()
+            .collect()
+    }
+
+    /// Time range covered by [Self::segments_between], which is cut at segment boundaries
+    pub fn bounds_between(&self, start: f32, end: f32) -> Option<(f32, f32)> {
+        let mut t = self.dropped;
+        let mut bounds: Option<(f32, f32)> = None;
+        for s in self.segments.iter() {
+            let seg_start = t;
+            t += s.duration;
+            if seg_start < end && t > start {
+                bounds = Some((bounds.map_or(seg_start, |b| b.0), t));
+            }
+        }
+        bounds
+    }
+
+    /// How often the playlist should be reloaded
+    pub fn reload_interval(&self) -> Duration {
+        Duration::from_secs_f32(self.target_duration.max(1.0))
+    }
+
+    /// Seconds of media between a player starting at the default live position and the edge
+    pub fn start_latency(&self) -> f32 {
+        self.segments
+            .iter()
+            .rev()
+            .take(LIVE_START_SEGMENTS)
+            .map(|s| s.duration)
+            .sum()
+    }
+
+    /// Time since the newest segment appeared, the edge keeps moving between reloads
+    pub fn edge_age(&self) -> f32 {
+        self.edge_seen
+            .map_or(0.0, |t| t.elapsed().as_secs_f32().min(self.target_duration))
+    }
+
+    /// Write a VOD playlist of the DVR window starting `behind` seconds from the live edge
+    pub fn write_dvr_playlist(&self, path: &Path, behind: f32) -> Result<()> {
+        let mut start = self.window() - behind;
+        let segments: Vec<HlsSegment> = self
+            .segments
+            .iter()
+            .skip_while(|s| {
+                start -= s.duration;
+                start > 0.0
+            })
+            .cloned()
+            .collect();
+        std::fs::write(path, write_media_playlist(&segments, self.init(), true))?;
+        Ok(())
+    }
+}
+
+/// Adaptive bitrate selection from measured download throughput
+pub struct AbrController {
+    samples: VecDeque<f64>,
+}
+
+impl AbrController {
+    pub fn new() -> Self {
+        Self {
+            samples: VecDeque::new(),
+        }
+    }
+
+    /// Record a download of `bytes` which took `secs`
+    pub fn add_sample(&mut self, bytes: usize, secs: f64) {
+        if secs <= 0.0 || bytes == 0 {
+            return;
+        }
+        self.samples.push_back(bytes as f64 * 8.0 / secs);
+        while self.samples.len() > ABR_SAMPLES {
+            self.samples.pop_front();
+        }
+    }
+
+    /// Estimated throughput in bits/s, the harmonic mean of recent samples
+    pub fn estimate(&self) -> Option<f64> {
+        if self.samples.is_empty() {
+            return None;
+        }
+        let inv: f64 = self.samples.iter().map(|s| 1.0 / s).sum();
+        Some(self.samples.len() as f64 / inv)
+    }
+
+    /// Pick a variant index from a list sorted by bandwidth (highest first).
+    ///
+    /// When the buffer is draining, step down one variant from `current`.
+    pub fn pick(&self, variants: &[HlsVariant], current: Option<usize>, starving: bool) -> usize {
+        if variants.is_empty() {
+            return 0;
+        }
+        let lowest = variants.len() - 1;
+        if starving {
+            return current.map_or(lowest, |c| (c + 1).min(lowest));
+        }
+        let budget = self.estimate().unwrap_or(ABR_DEFAULT_BPS) * ABR_SAFETY;
+        variants
+            .iter()
+            .position(|v| (v.bandwidth as f64) <= budget)
+            .unwrap_or(lowest)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fixture_path(name: &str) -> String {
+        format!("{}/tests/fixtures/hls/{}", env!("CARGO_MANIFEST_DIR"), name)
+    }
+
+    fn fixture(name: &str) -> Vec<u8> {
+        load_playlist(&Context::default(), &fixture_path(name))
+            .block_and_take()
+            .expect("fixture")
+            .bytes
+    }
+
+    fn tracker(names: &[&str]) -> LiveTracker {
+        let mut t = LiveTracker::new(&fixture_path(names[0]));
+        for name in names {
+            t.update(&fixture(name)).unwrap();
+        }
+        t
+    }
+
+    fn variants() -> Vec<HlsVariant> {
+        parse_master(&fixture_path("master.m3u8"), &fixture("master.m3u8")).unwrap()
+    }
+
+    #[test]
+    fn parse_master_sorts_video_variants() {
+        let v = variants();
+        assert_eq!(
+            v.iter().map(|v| v.bandwidth).collect::<Vec<_>>(),
+            vec![5_000_000, 2_500_000, 800_000]
+        );
+        assert_eq!(v[0].url, fixture_path("1080p/index.m3u8"));
+        assert_eq!(v[0].label(), "1080p");
+
+        let audio = parse_audio(&fixture_path("master.m3u8"), &fixture("master.m3u8"));
+        assert_eq!(audio, Some(fixture_path("audio/index.m3u8")));
+    }
+
+    #[test]
+    fn parse_master_of_media_playlist_is_empty() {
+        let v = parse_master(&fixture_path("live_1.m3u8"), &fixture("live_1.m3u8")).unwrap();
+        assert!(v.is_empty());
+    }
+
+    #[test]
+    fn live_tracker_merges_reloads() {
+        let t = tracker(&["live_1.m3u8"]);
+        assert_eq!(t.window(), 16.0);
+        assert!(!t.ended());
+
+        let t = tracker(&["live_1.m3u8", "live_2.m3u8"]);
+        let seqs: Vec<u64> = t.segments.iter().map(|s| s.sequence).collect();
+        assert_eq!(seqs, vec![10, 11, 12, 13, 14, 15]);
+        assert_eq!(t.window(), 24.0);
+        assert_eq!(t.edge_time(), 24.0);
+        assert_eq!(t.start_latency(), 12.0);
+        assert_eq!(t.reload_interval(), Duration::from_secs(4));
+        assert_eq!(t.segments.back().unwrap().url, fixture_path("15.ts"));
+
+        let live = t.segments_between(20.0, 24.0);
+        assert_eq!(live.len(), 1);
+        assert_eq!(live[0].sequence, 15);
+        assert_eq!(t.bounds_between(21.0, 23.0), Some((20.0, 24.0)));
+    }
+
+    #[test]
+    fn live_tracker_slides_dvr_window() {
+        let t = tracker(&["long_1.m3u8", "long_2.m3u8"]);
+        assert_eq!(t.segments.front().unwrap().sequence, 1);
+        assert_eq!(t.window(), DVR_WINDOW_SECS);
+        assert_eq!(t.edge_time(), 3.0 * 3600.0);
+
+        // timeline keeps the dropped segment
+        let s = t.segments_between(3600.0, 3601.0);
+        assert_eq!(s[0].sequence, 1);
+    }
+
+    #[test]
+    fn dvr_playlist_starts_behind_edge() {
+        let t = tracker(&["live_1.m3u8", "live_2.m3u8"]);
+        let path = std::env::temp_dir().join("zap-stream-test-dvr.m3u8");
+        t.write_dvr_playlist(&path, 10.0).unwrap();
+        let data = std::fs::read(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        let playlist = match m3u8_rs::parse_playlist_res(&data).unwrap() {
+            Playlist::MediaPlaylist(m) => m,
+            Playlist::MasterPlaylist(_) => panic!("Expected media playlist"),
+        };
+        assert!(playlist.end_list);
+        assert_eq!(playlist.media_sequence, 13);
+        assert_eq!(playlist.segments.len(), 3);
+    }
+
+    #[test]
+    fn abr_picks_variant_within_budget() {
+        let v = variants();
+        let mut abr = AbrController::new();
+        assert_eq!(abr.pick(&[], None, false), 0);
+
+        // default estimate fits 800k only
+        assert_eq!(abr.pick(&v, None, false), 2);
+
+        abr.add_sample(1_250_000, 1.0);
+        assert_eq!(abr.pick(&v, None, false), 0);
+
+        // harmonic mean of 10M and 2M is 3.3M
+        abr.add_sample(250_000, 1.0);
+        assert_eq!(abr.pick(&v, None, false), 1);
+    }
+
+    #[test]
+    fn abr_steps_down_when_starving() {
+        let v = variants();
+        let abr = AbrController::new();
+        assert_eq!(abr.pick(&v, Some(0), true), 1);
+        assert_eq!(abr.pick(&v, Some(2), true), 2);
+        assert_eq!(abr.pick(&v, None, true), 2);
+    }
+}
@@ -1,13 +1,23 @@
 #[cfg(target_os = "android")]
 mod android;
 pub mod app;
+mod contacts;
+mod hls;
 mod link;
+mod n94;
+mod nip05;
 mod note_ref;
 mod note_util;
 mod note_view;
+mod outbox;
+pub mod overlay;
+mod playback;
+mod player_manager;
 mod profiles;
+mod relay_results;
 mod route;
 mod services;
+mod settings;
 mod stream_info;
 mod sub;
 mod theme;
@@ -1,5 +1,6 @@
 use crate::note_util::NoteUtil;
-use bech32::{Hrp, NoChecksum};
+use anyhow::{anyhow, bail};
+use bech32::{Bech32, Hrp, NoChecksum};
 use nostr::prelude::{hex, Coordinate};
 use nostr::{Kind, PublicKey};
 use nostrdb::{Filter, NdbStrVariant, Note};
@@ -94,6 +95,80 @@ impl NostrLink {
         }
     }
 
+    /// Parse a bech32 encoded nostr entity, with or without the nostr: prefix
+    pub fn from_bech32(s: &str) -> anyhow::Result<Self> {
+        let s = s.trim().trim_start_matches("nostr:");
+        let (hrp, data) = bech32::decode(s)?;
+        let hrp = match hrp.to_lowercase().as_str() {
+            "note" => NostrLinkType::Note,
+            "npub" => NostrLinkType::PublicKey,
+            "nsec" => NostrLinkType::PrivateKey,
+            "nevent" => NostrLinkType::Event,
+            "nprofile" => NostrLinkType::Profile,
+            "naddr" => NostrLinkType::Coordinate,
+            h => bail!("Unknown prefix {}", h),
+        };
+        match hrp {
+            NostrLinkType::Note | NostrLinkType::PublicKey | NostrLinkType::PrivateKey => {
+                let id: [u8; 32] = data
+                    .as_slice()
+                    .try_into()
+                    .map_err(|_| anyhow!("Invalid id length"))?;
+                Ok(Self::new(hrp, IdOrStr::Id(id), None, None, vec![]))
+            }
+            _ => {
+                let mut id = None;
+                let mut kind = None;
+                let mut author = None;
+                let mut relays = vec![];
+                let mut buf = data.as_slice();
+                while buf.len() >= 2 {
+                    let (t, l) = (buf[0], buf[1] as usize);
+                    if buf.len() < 2 + l {
+                        bail!("Invalid TLV length");
+                    }
+                    let v = &buf[2..2 + l];
+                    match t {
+                        0 => {
+                            id = Some(if hrp == NostrLinkType::Coordinate {
+                                IdOrStr::Str(String::from_utf8(v.to_vec())?)
+                            } else {
+                                IdOrStr::Id(v.try_into()?)
+                            })
+                        }
+                        1 => relays.push(String::from_utf8(v.to_vec())?),
+                        2 => author = Some(v.try_into()?),
+                        3 => kind = Some(u32::from_be_bytes(v.try_into()?)),
+                        _ => {}
+                    }
+                    buf = &buf[2 + l..];
+                }
+                let id = id.ok_or(anyhow!("Missing id"))?;
+                if hrp == NostrLinkType::Coordinate && (kind.is_none() || author.is_none()) {
+                    bail!("Missing kind or author");
+                }
+                Ok(Self::new(hrp, id, kind, author, relays))
+            }
+        }
+    }
+
+    /// Parse an `a` tag value (kind:pubkey:identifier)
+    pub fn from_tag_value(s: &str) -> anyhow::Result<Self> {
+        let mut parts = s.splitn(3, ':');
+        let kind = parts.next().ok_or(anyhow!("Missing kind"))?.parse()?;
+        let author = hex::decode(parts.next().ok_or(anyhow!("Missing author"))?)?
+            .try_into()
+            .map_err(|_| anyhow!("Invalid author"))?;
+        let id = parts.next().ok_or(anyhow!("Missing identifier"))?;
+        Ok(Self::new(
+            NostrLinkType::Coordinate,
+            IdOrStr::Str(id.to_string()),
+            Some(kind),
+            Some(author),
+            vec![],
+        ))
+    }
+
     pub fn profile(pubkey: &[u8; 32]) -> Self {
         Self {
             hrp: NostrLinkType::Profile,
@@ -181,7 +256,26 @@ impl Display for NostrLink {
                 )
                 .map_err(|e| std::fmt::Error)?)
             }
-            NostrLinkType::Event | NostrLinkType::Profile | NostrLinkType::Coordinate => todo!(),
+            NostrLinkType::Event | NostrLinkType::Profile | NostrLinkType::Coordinate => {
+                let mut tlv = Vec::new();
+                let mut push = |t: u8, v: &[u8]| {
+                    tlv.push(t);
+                    tlv.push(v.len() as u8);
+                    tlv.extend_from_slice(v);
+                };
+                push(0, self.id.as_bytes());
+                for r in self.relays.iter() {
+                    push(1, r.as_bytes());
+                }
+                if let Some(author) = &self.author {
+                    push(2, author);
+                }
+                if let Some(kind) = self.kind {
+                    push(3, &kind.to_be_bytes());
+                }
+                bech32::encode_to_fmt::<Bech32, Formatter>(f, self.hrp.to_hrp(), &tlv)
+                    .map_err(|_| std::fmt::Error)
+            }
         }
     }
 }
@@ -0,0 +1,242 @@
+use crate::hls::{write_media_playlist, HlsSegment};
+use crate::link::NostrLink;
+use crate::note_ref::NoteRef;
+use crate::note_util::NoteUtil;
+use crate::route::RouteServices;
+use crate::sub::SubRef;
+use crate::widgets::sub_or_poll;
+use nostrdb::{Filter, Note};
+use std::collections::{BTreeMap, HashSet};
+use std::path::PathBuf;
+
+/// Segment of a n94 stream, a NIP-94 file metadata event (kind 1063) with the `a` tag of the stream
+///
+/// https://github.com/nostr-protocol/nips/blob/master/94.md
+pub const N94_SEGMENT_KIND: u64 = 1063;
+
+/// Builds a playable HLS playlist from the segment events of a kind 30313 n94 stream
+pub struct N94Stream {
+    link: NostrLink,
+    events: HashSet<NoteRef>,
+    sub: Option<SubRef>,
+
+    /// Segments ordered by their index
+    segments: BTreeMap<u64, HlsSegment>,
+    /// Number of events the segments were built from
+    parsed: usize,
+    /// Local playlist which is handed to the player
+    path: PathBuf,
+    /// Segment count and ended flag of the playlist on disk
+    written: Option<(usize, bool)>,
+}
+
+impl N94Stream {
+    pub fn new(link: NostrLink) -> Self {
+        let path = std::env::temp_dir().join(format!(
+            "zap-stream-n94-{}.m3u8",
+            egui::Id::new(&link).value()
+        ));
+        Self {
+            link,
+            events: HashSet::new(),
+            sub: None,
+            segments: BTreeMap::new(),
+            parsed: 0,
+            path,
+            written: None,
+        }
+    }
+
+    fn get_filter(&self) -> Filter {
+        Filter::new()
+            .kinds([N94_SEGMENT_KIND])
+            .tags([self.link.to_tag_value()], 'a')
+            .build()
+    }
+
+    pub fn update(&mut self, services: &mut RouteServices<'_, '_>) -> anyhow::Result<()> {
+        let filters = vec![self.get_filter()];
+        sub_or_poll(
+            services.ctx.ndb,
+            services.tx,
+            services.ctx.pool,
+            &mut self.events,
+            &mut self.sub,
+            filters,
+        )?;
+        if self.events.len() != self.parsed {
+            let notes: Vec<Note> = self
+                .events
+                .iter()
+                .filter_map(|e| services.ctx.ndb.get_note_by_key(services.tx, e.key).ok())
+                .collect();
+            self.set_segments(&notes);
+            self.parsed = self.events.len();
+        }
+        Ok(())
+    }
+
+    /// Rebuild the segment list from segment events
+    fn set_segments(&mut self, notes: &[Note<'_>]) {
+        let stream = self.link.to_tag_value();
+        self.segments = notes
+            .iter()
+            .filter_map(|n| parse_segment(n, &stream))
+            .collect();
+    }
+
+    /// Write the playlist of all known segments, returns the path once there is something to play
+    pub fn write_playlist(&mut self, ended: bool) -> anyhow::Result<Option<String>> {
+        if self.segments.is_empty() {
+            return Ok(None);
+        }
+        let state = (self.segments.len(), ended);
+        if self.written != Some(state) {
+            let segments: Vec<HlsSegment> = self.segments.values().cloned().collect();
+            std::fs::write(&self.path, write_media_playlist(&segments, None, ended))?;
+            self.written = Some(state);
+        }
+        Ok(Some(self.path.to_string_lossy().to_string()))
+    }
+}
+
+/// Segment position and media from a segment event of `stream` (`a` tag value),
+/// the index tag orders the segments
+fn parse_segment(note: &Note<'_>, stream: &str) -> Option<(u64, HlsSegment)> {
+    if note.kind() as u64 != N94_SEGMENT_KIND {
+        return None;
+    }
+    let tag = |k: &str| note.get_tag_value(k).and_then(|v| v.variant().str());
+    if tag("a")? != stream {
+        return None;
+    }
+    let url = tag("url")?;
+    let duration: f32 = tag("duration")?.parse().ok()?;
+    let index = tag("index")?.parse().ok()?;
+    Some((
+        index,
+        HlsSegment {
+            sequence: index,
+            url: url.to_string(),
+            duration,
+        },
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::link::{IdOrStr, NostrLinkType};
+    use nostrdb::NoteBuilder;
+
+    const KEY: [u8; 32] = [1; 32];
+
+    fn stream(id: &str) -> N94Stream {
+        N94Stream::new(NostrLink::new(
+            NostrLinkType::Coordinate,
+            IdOrStr::Str(id.to_string()),
+            Some(30_313),
+            Some([2; 32]),
+            vec![],
+        ))
+    }
+
+    fn segment(kind: u32, a: Option<&str>, index: u64) -> Note<'static> {
+        let mut b = NoteBuilder::new()
+            .kind(kind)
+            .content("")
+            .created_at(1_700_000_000 + index)
+            .start_tag()
+            .tag_str("url")
+            .tag_str(&format!("https://example.com/{}.ts", index))
+            .start_tag()
+            .tag_str("duration")
+            .tag_str("2.000")
+            .start_tag()
+            .tag_str("index")
+            .tag_str(&index.to_string());
+        if let Some(a) = a {
+            b = b.start_tag().tag_str("a").tag_str(a);
+        }
+        b.sign(&KEY).build().expect("note")
+    }
+
+    fn playlist(stream: &mut N94Stream, ended: bool) -> String {
+        let path = stream.write_playlist(ended).unwrap().expect("playlist");
+        std::fs::read_to_string(path).unwrap()
+    }
+
+    #[test]
+    fn parse_segment_checks_kind_and_stream() {
+        let s = stream("parse").link.to_tag_value();
+        let (index, seg) = parse_segment(&segment(1063, Some(&s), 4), &s).unwrap();
+        assert_eq!(index, 4);
+        assert_eq!(seg.url, "https://example.com/4.ts");
+        assert_eq!(seg.duration, 2.0);
+
+        assert!(parse_segment(&segment(1053, Some(&s), 4), &s).is_none());
+        assert!(parse_segment(&segment(1063, None, 4), &s).is_none());
+        assert!(parse_segment(&segment(1063, Some("30313:other:d"), 4), &s).is_none());
+    }
+
+    #[test]
+    fn playlist_is_ordered_by_index() {
+        let mut s = stream("ordering");
+        let a = s.link.to_tag_value();
+        let notes: Vec<Note> = [2, 0, 1]
+            .into_iter()
+            .map(|i| segment(1063, Some(&a), i))
+            .collect();
+        s.set_segments(&notes);
+
+        let m3u8 = playlist(&mut s, false);
+        let urls: Vec<&str> = m3u8.lines().filter(|l| l.starts_with("https")).collect();
+        assert_eq!(
+            urls,
+            vec![
+                "https://example.com/0.ts",
+                "https://example.com/1.ts",
+                "https://example.com/2.ts"
+            ]
+        );
+        assert!(m3u8.contains("#EXT-X-MEDIA-SEQUENCE:0\n"));
+        assert!(!m3u8.contains("#EXT-X-DISCONTINUITY"));
+        assert!(!m3u8.contains("#EXT-X-ENDLIST"));
+        assert!(playlist(&mut s, true).ends_with("#EXT-X-ENDLIST\n"));
+    }
+
+    #[test]
+    fn playlist_marks_gaps() {
+        let mut s = stream("gaps");
+        let a = s.link.to_tag_value();
+        let notes: Vec<Note> = [3, 4, 7]
+            .into_iter()
+            .map(|i| segment(1063, Some(&a), i))
+            .collect();
+        s.set_segments(&notes);
+
+        let m3u8 = playlist(&mut s, true);
+        assert!(m3u8.contains("#EXT-X-MEDIA-SEQUENCE:3\n"));
+        assert!(
+            m3u8.contains("4.ts\n#EXT-X-DISCONTINUITY\n#EXTINF:2.000,\nhttps://example.com/7.ts")
+        );
+        assert_eq!(m3u8.matches("#EXT-X-DISCONTINUITY").count(), 1);
+    }
+
+    #[test]
+    fn playlist_skips_invalid_events() {
+        let mut s = stream("invalid");
+        let a = s.link.to_tag_value();
+        s.set_segments(&[segment(1053, Some(&a), 0), segment(1063, None, 1)]);
+        assert_eq!(s.write_playlist(false).unwrap(), None);
+
+        s.set_segments(&[
+            segment(1053, Some(&a), 0),
+            segment(1063, None, 1),
+            segment(1063, Some(&a), 2),
+        ]);
+        let m3u8 = playlist(&mut s, false);
+        assert_eq!(m3u8.matches("#EXTINF").count(), 1);
+        assert!(m3u8.contains("https://example.com/2.ts"));
+    }
+}
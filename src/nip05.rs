@@ -0,0 +1,142 @@
+use nostr::prelude::hex;
+use nostr::serde_json;
+use serde::Deserialize;
+use std::collections::{HashMap, HashSet};
+use std::time::{Duration, Instant};
+
+/// How long a verification result is used before checking again
+const CACHE_TTL: Duration = Duration::from_secs(60 * 60);
+
+/// What to show next to a name
+#[derive(Debug, Clone, PartialEq)]
+pub enum Nip05Badge {
+    /// Verified identifier, with the domain shown next to the name
+    Verified(String),
+    /// Name looks like the name of another verified user
+    Lookalike,
+    None,
+}
+
+struct Nip05Entry {
+    nip05: String,
+    verified: bool,
+    checked: Instant,
+}
+
+/// Verification results of profile NIP-05 identifiers
+pub struct Nip05Cache {
+    entries: HashMap<[u8; 32], Nip05Entry>,
+    /// Pubkeys of verified users by name skeleton
+    verified_names: HashMap<String, HashSet<[u8; 32]>>,
+}
+
+impl Nip05Cache {
+    pub fn new() -> Self {
+        Self {
+            entries: HashMap::new(),
+            verified_names: HashMap::new(),
+        }
+    }
+
+    /// Cached result for this identifier, `None` when unknown or expired
+    pub fn get(&self, pubkey: &[u8; 32], nip05: &str) -> Option<bool> {
+        self.entries
+            .get(pubkey)
+            .filter(|e| e.nip05 == nip05 && e.checked.elapsed() < CACHE_TTL)
+            .map(|e| e.verified)
+    }
+
+    pub fn insert(&mut self, pubkey: &[u8; 32], nip05: &str, verified: bool, name: Option<&str>) {
+        self.entries.insert(
+            *pubkey,
+            Nip05Entry {
+                nip05: nip05.to_string(),
+                verified,
+                checked: Instant::now(),
+            },
+        );
+        if let Some(name) = name.map(skeleton).filter(|n| !n.is_empty()) {
+            let pubkeys = self.verified_names.entry(name).or_default();
+            if verified {
+                pubkeys.insert(*pubkey);
+            } else {
+                pubkeys.remove(pubkey);
+            }
+        }
+    }
+
+    /// The name looks like the name of a verified user with another pubkey
+    pub fn is_lookalike(&self, pubkey: &[u8; 32], name: &str) -> bool {
+        self.verified_names
+            .get(&skeleton(name))
+            .map_or(false, |p| !p.is_empty() && !p.contains(pubkey))
+    }
+}
+
+/// Lowercase name without separators and with common lookalike characters folded,
+/// so `Jack`, `jack_` and `JACK` compare equal
+fn skeleton(name: &str) -> String {
+    name.to_lowercase()
+        .chars()
+        .filter(|c| c.is_alphanumeric())
+        .map(|c| match c {
+            '0' => 'o',
+            '1' | 'i' => 'l',
+            '3' => 'e',
+            '4' => 'a',
+            '5' => 's',
+            '7' => 't',
+            'а' => 'a',
+            'е' => 'e',
+            'о' => 'o',
+            'р' => 'p',
+            'с' => 'c',
+            'х' => 'x',
+            c => c,
+        })
+        .collect()
+}
+
+#[derive(Deserialize)]
+struct NostrJson {
+    #[serde(default)]
+    names: HashMap<String, String>,
+}
+
+/// Split a NIP-05 identifier into lowercase name and domain
+pub fn parse(nip05: &str) -> Option<(String, String)> {
+    let nip05 = nip05.trim().to_lowercase();
+    let (name, domain) = match nip05.split_once('@') {
+        Some((n, d)) => (n.to_string(), d.to_string()),
+        // bare domain
+        None => ("_".to_string(), nip05),
+    };
+    if name.is_empty() || domain.is_empty() || !domain.contains('.') {
+        return None;
+    }
+    Some((name, domain))
+}
+
+/// Url of the nostr.json listing the name
+pub fn url(name: &str, domain: &str) -> String {
+    format!("https://{}/.well-known/nostr.json?name={}", domain, name)
+}
+
+/// The nostr.json maps the name to the pubkey
+pub fn verify(json: &[u8], name: &str, pubkey: &[u8; 32]) -> bool {
+    match serde_json::from_slice::<NostrJson>(json) {
+        Ok(j) => j.names.iter().any(|(n, pk)| {
+            n.to_lowercase() == name && pk.eq_ignore_ascii_case(&hex::encode(pubkey))
+        }),
+        Err(_) => false,
+    }
+}
+
+/// Identifier for display, the `_` name is shown as just the domain
+pub fn display(nip05: &str) -> String {
+    match parse(nip05) {
+        Some((name, domain)) if name == "_" => domain,
+        Some((name, domain)) => format!("{}@{}", name, domain),
+        None => nip05.to_string(),
+    }
+}
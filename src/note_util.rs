@@ -7,6 +7,9 @@ pub trait NoteUtil {
     fn find_tag_value<F>(&self, fx: F) -> Option<NdbStr>
     where
         F: Fn(Vec<NdbStr>) -> bool;
+
+    /// Custom emoji (NIP-30) as (shortcode, url)
+    fn emojis(&self) -> Vec<(String, String)>;
 }
 
 impl NoteUtil for Note<'_> {
@@ -32,6 +35,20 @@ impl NoteUtil for Note<'_> {
             None
         }
     }
+
+    fn emojis(&self) -> Vec<(String, String)> {
+        self.tags()
+            .iter()
+            .filter_map(|t| {
+                if t.count() < 3 || t.get(0)?.variant().str() != Some("emoji") {
+                    return None;
+                }
+                let code = t.get(1)?.variant().str()?;
+                let url = t.get(2)?.variant().str()?;
+                Some((code.to_string(), url.to_string()))
+            })
+            .collect()
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -0,0 +1,151 @@
+use enostr::{ClientMessage, EventClientMessage, PoolRelay, RelayPool};
+use log::{info, warn};
+use nostr::prelude::hex;
+use nostr::serde_json;
+use std::collections::HashMap;
+use std::time::Instant;
+
+/// Max number of pending or rejected events kept, the oldest are dropped first
+const MAX_EVENTS: usize = 100;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum PublishState {
+    /// Waiting for an OK from any relay
+    Pending,
+    /// At least one relay accepted the event
+    Sent,
+    /// All relays rejected the event
+    Failed(String),
+}
+
+struct OutboxEvent {
+    json: String,
+    relays: HashMap<String, PublishState>,
+    published: Instant,
+}
+
+/// Tracks published events until relays acknowledge them
+pub struct Outbox {
+    events: HashMap<[u8; 32], OutboxEvent>,
+}
+
+impl Outbox {
+    pub fn new() -> Self {
+        Self {
+            events: HashMap::new(),
+        }
+    }
+
+    /// Send an event to all relays in the pool
+    pub fn publish(&mut self, pool: &mut RelayPool, id: [u8; 32], json: String) {
+        let relays = pool
+            .relays
+            .iter()
+            .filter_map(|r| match r {
+                PoolRelay::Websocket(w) => Some((w.relay.url.to_string(), PublishState::Pending)),
+                _ => None,
+            })
+            .collect();
+        pool.send(&ClientMessage::Event(EventClientMessage {
+            note_json: json.clone(),
+        }));
+        self.events.insert(
+            id,
+            OutboxEvent {
+                json,
+                relays,
+                published: Instant::now(),
+            },
+        );
+    }
+
+    /// Handle the raw text of an OK message.
+    ///
+    /// [enostr::RelayMessage::OK] doesn't expose the parsed result, so only OK
+    /// messages are parsed again here.
+    pub fn handle_ok(&mut self, relay: &str, msg: &str) {
+        let msg: Vec<serde_json::Value> = match serde_json::from_str(msg) {
+            Ok(m) => m,
+            Err(_) => return,
+        };
+        if msg.len() < 3 || msg[0].as_str() != Some("OK") {
+            return;
+        }
+        let id: [u8; 32] = match msg[1]
+            .as_str()
+            .and_then(|i| hex::decode(i).ok())
+            .and_then(|i| i.try_into().ok())
+        {
+            Some(i) => i,
+            None => return,
+        };
+        let accepted = msg[2].as_bool().unwrap_or(false);
+        let reason = msg.get(3).and_then(|m| m.as_str()).unwrap_or("");
+
+        if let Some(ev) = self.events.get_mut(&id) {
+            let state = if accepted {
+                PublishState::Sent
+            } else {
+                warn!(
+                    "Event {} rejected by {}: {}",
+                    hex::encode(id),
+                    relay,
+                    reason
+                );
+                PublishState::Failed(reason.to_string())
+            };
+            ev.relays.insert(relay.to_string(), state);
+        }
+        self.evict();
+    }
+
+    /// Forget events once every relay answered and one accepted it,
+    /// rejected events are kept to show the reason
+    fn evict(&mut self) {
+        self.events.retain(|_, ev| {
+            ev.relays.values().any(|s| *s == PublishState::Pending)
+                || !ev.relays.values().any(|s| *s == PublishState::Sent)
+        });
+        let count = self.events.len();
+        if count > MAX_EVENTS {
+            let mut by_age: Vec<([u8; 32], Instant)> =
+                self.events.iter().map(|(k, e)| (*k, e.published)).collect();
+            by_age.sort_by_key(|(_, t)| *t);
+            for (k, _) in by_age.into_iter().take(count - MAX_EVENTS) {
+                self.events.remove(&k);
+            }
+        }
+    }
+
+    /// Re-send any events which this relay hasn't answered yet
+    pub fn retry(&mut self, pool: &mut RelayPool, relay: &str) {
+        for ev in self.events.values() {
+            if let Some(state) = ev.relays.get(relay) {
+                if *state == PublishState::Pending {
+                    info!("Retrying publish to {}", relay);
+                    pool.send_to(
+                        &ClientMessage::Event(EventClientMessage {
+                            note_json: ev.json.clone(),
+                        }),
+                        relay,
+                    );
+                }
+            }
+        }
+    }
+
+    /// Combined publish state across all relays
+    pub fn state(&self, id: &[u8; 32]) -> Option<PublishState> {
+        let ev = self.events.get(id)?;
+        if ev.relays.values().any(|s| *s == PublishState::Sent) {
+            return Some(PublishState::Sent);
+        }
+        if ev.relays.values().any(|s| *s == PublishState::Pending) {
+            return Some(PublishState::Pending);
+        }
+        ev.relays.values().find_map(|s| match s {
+            PublishState::Failed(r) => Some(PublishState::Failed(r.clone())),
+            _ => None,
+        })
+    }
+}
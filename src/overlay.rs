@@ -0,0 +1,77 @@
+use crate::link::NostrLink;
+use anyhow::{anyhow, bail, Result};
+use egui::Color32;
+
+/// Settings for the chat overlay mode (`--overlay <naddr>`)
+#[derive(Clone)]
+pub struct OverlayConfig {
+    pub link: NostrLink,
+    /// Seconds after which messages fade out, 0 keeps messages forever
+    pub fade_secs: u64,
+    pub font_size: f32,
+    pub show_chat: bool,
+    pub show_zaps: bool,
+    pub background: Color32,
+}
+
+impl OverlayConfig {
+    /// Parse overlay options from command line args, returns None when not in overlay mode
+    ///
+    /// ```text
+    /// --overlay <naddr>
+    /// --overlay-fade <seconds>
+    /// --overlay-font-size <px>
+    /// --overlay-kinds chat,zaps
+    /// --overlay-bg transparent|#00ff00
+    /// ```
+    pub fn from_args(args: &[String]) -> Result<Option<Self>> {
+        let mut link = None;
+        let mut fade_secs = 30;
+        let mut font_size = 24.0;
+        let mut show_chat = true;
+        let mut show_zaps = true;
+        let mut background = Color32::from_rgb(0, 255, 0);
+
+        let mut iter = args.iter();
+        while let Some(arg) = iter.next() {
+            let mut value = || {
+                iter.next()
+                    .ok_or(anyhow!("Missing value for {}", arg))
+                    .map(|v| v.as_str())
+            };
+            match arg.as_str() {
+                "--overlay" => link = Some(NostrLink::from_bech32(value()?)?),
+                "--overlay-fade" => fade_secs = value()?.parse()?,
+                "--overlay-font-size" => font_size = value()?.parse()?,
+                "--overlay-kinds" => {
+                    let kinds: Vec<&str> = value()?.split(',').map(|k| k.trim()).collect();
+                    show_chat = kinds.contains(&"chat");
+                    show_zaps = kinds.contains(&"zaps");
+                }
+                "--overlay-bg" => background = parse_color(value()?)?,
+                _ => {}
+            }
+        }
+
+        Ok(link.map(|link| Self {
+            link,
+            fade_secs,
+            font_size,
+            show_chat,
+            show_zaps,
+            background,
+        }))
+    }
+}
+
+fn parse_color(s: &str) -> Result<Color32> {
+    if s == "transparent" {
+        return Ok(Color32::TRANSPARENT);
+    }
+    let hex = s.trim_start_matches('#');
+    if hex.len() != 6 {
+        bail!("Invalid color {}", s);
+    }
+    let v = u32::from_str_radix(hex, 16)?;
+    Ok(Color32::from_rgb((v >> 16) as u8, (v >> 8) as u8, v as u8))
+}
@@ -0,0 +1,79 @@
+use nostr::Url;
+use std::cmp::Reverse;
+
+/// A kind of media url the ffmpeg backend can open
+struct Capability {
+    /// Url schemes, `file` is also used for plain local paths
+    schemes: &'static [&'static str],
+    /// File extensions, empty matches any path
+    extensions: &'static [&'static str],
+    /// Higher is preferred when an event has multiple urls
+    priority: u8,
+}
+
+const HTTP: &[&str] = &["http", "https", "file"];
+
+/// What the player can open, by url scheme and file extension
+const CAPABILITIES: &[Capability] = &[
+    // HLS, adaptive quality and DVR
+    Capability {
+        schemes: HTTP,
+        extensions: &["m3u8"],
+        priority: 100,
+    },
+    // DASH
+    Capability {
+        schemes: HTTP,
+        extensions: &["mpd"],
+        priority: 80,
+    },
+    // Progressive files / recordings
+    Capability {
+        schemes: HTTP,
+        extensions: &["mp4", "m4v", "webm", "mkv", "mov", "ts", "flv"],
+        priority: 60,
+    },
+    // Pull urls of live ingest servers
+    Capability {
+        schemes: &["rtmp", "rtmps", "rtsp"],
+        extensions: &[],
+        priority: 40,
+    },
+    Capability {
+        schemes: &["srt"],
+        extensions: &[],
+        priority: 30,
+    },
+];
+
+/// Playback priority of a url, None when the player can't open it
+pub fn priority(url: &str) -> Option<u8> {
+    let (scheme, path) = match Url::parse(url) {
+        // windows drive letters parse as a single letter scheme
+        Ok(u) if u.scheme().len() > 1 => (u.scheme().to_string(), u.path().to_string()),
+        _ => ("file".to_string(), url.to_string()),
+    };
+    let ext = path
+        .rsplit_once('.')
+        .map(|(_, e)| e.to_lowercase())
+        .unwrap_or_default();
+    CAPABILITIES
+        .iter()
+        .filter(|c| c.schemes.contains(&scheme.as_str()))
+        .filter(|c| c.extensions.is_empty() || c.extensions.contains(&ext.as_str()))
+        .map(|c| c.priority)
+        .max()
+}
+
+pub fn can_play(url: &str) -> bool {
+    priority(url).is_some()
+}
+
+/// The url with the highest playback priority, the first one wins a tie
+pub fn best_url<'a>(urls: impl IntoIterator<Item = &'a str>) -> Option<&'a str> {
+    urls.into_iter()
+        .enumerate()
+        .filter_map(|(i, u)| priority(u).map(|p| (p, Reverse(i), u)))
+        .max_by_key(|(p, i, _)| (*p, *i))
+        .map(|(_, _, u)| u)
+}
@@ -0,0 +1,88 @@
+use crate::link::NostrLink;
+use crate::settings::BackgroundPlayback;
+use crate::widgets::{MiniPlayer, MiniPlayerAction, StreamPlayer};
+use egui::Context;
+
+struct ActivePlayer {
+    link: NostrLink,
+    title: String,
+    /// Playing the recording of an ended stream
+    replay: bool,
+    player: StreamPlayer,
+}
+
+/// Owns the stream player so playback survives navigation.
+///
+/// The stream page borrows the player while it is shown, on other pages it keeps
+/// playing in the mini player or audio only bar.
+pub struct PlayerManager {
+    active: Option<ActivePlayer>,
+    /// Stream page of the active player is open
+    attached: bool,
+}
+
+impl PlayerManager {
+    pub fn new() -> Self {
+        Self {
+            active: None,
+            attached: false,
+        }
+    }
+
+    /// Player for this stream, if it's playing the stream or recording as requested
+    pub fn get(&mut self, link: &NostrLink, replay: bool) -> Option<&mut StreamPlayer> {
+        self.active
+            .as_mut()
+            .filter(|a| &a.link == link && a.replay == replay)
+            .map(|a| &mut a.player)
+    }
+
+    /// Replace the active player
+    pub fn play(&mut self, link: NostrLink, title: &str, replay: bool, player: StreamPlayer) {
+        self.active = Some(ActivePlayer {
+            link,
+            title: title.to_string(),
+            replay,
+            player,
+        });
+    }
+
+    pub fn stop(&mut self) {
+        self.active = None;
+    }
+
+    /// Stream page opened, any other stream stops playing
+    pub fn attach(&mut self, link: &NostrLink) {
+        if self.active.as_ref().map_or(false, |a| &a.link != link) {
+            self.active = None;
+        }
+        self.attached = true;
+    }
+
+    /// Stream page closed, continue in the background
+    pub fn detach(&mut self) {
+        self.attached = false;
+    }
+
+    /// Stream of the background player
+    pub fn link(&self) -> Option<&NostrLink> {
+        self.active.as_ref().map(|a| &a.link)
+    }
+
+    /// Show the background player when the stream page isn't open
+    pub fn show(&mut self, ctx: &Context, mode: BackgroundPlayback) -> Option<MiniPlayerAction> {
+        if self.attached {
+            return None;
+        }
+        let active = self.active.as_mut()?;
+        if mode == BackgroundPlayback::AudioOnly {
+            active.player.set_audio_only(true);
+        }
+        MiniPlayer::new(
+            &mut active.player,
+            &active.title,
+            mode == BackgroundPlayback::AudioOnly,
+        )
+        .show(ctx)
+    }
+}
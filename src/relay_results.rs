@@ -0,0 +1,79 @@
+use enostr::RelayPool;
+use nostr::prelude::hex;
+use nostr::serde_json;
+use nostrdb::Filter;
+use std::collections::{HashMap, HashSet};
+
+/// Remembers which events relays sent for a subscription, for queries like NIP-50
+/// search which the local database can't answer, and which relays finished
+/// sending stored events
+pub struct RelayResults {
+    subs: HashMap<String, SubResults>,
+}
+
+#[derive(Default)]
+struct SubResults {
+    ids: HashSet<[u8; 32]>,
+    /// Relays which sent EOSE
+    eose: HashSet<String>,
+}
+
+impl RelayResults {
+    pub fn new() -> Self {
+        Self {
+            subs: HashMap::new(),
+        }
+    }
+
+    pub fn subscribe(&mut self, pool: &mut RelayPool, id: String, filters: Vec<Filter>) {
+        self.subs.insert(id.clone(), SubResults::default());
+        pool.subscribe(id, filters);
+    }
+
+    /// Track a subscription which was sent to the relays elsewhere
+    pub fn watch(&mut self, id: String) {
+        self.subs.entry(id).or_default();
+    }
+
+    pub fn unsubscribe(&mut self, pool: &mut RelayPool, id: &str) {
+        if self.subs.remove(id).is_some() {
+            pool.unsubscribe(id.to_string());
+        }
+    }
+
+    /// Event ids received for a subscription
+    pub fn get(&self, id: &str) -> Option<&HashSet<[u8; 32]>> {
+        self.subs.get(id).map(|s| &s.ids)
+    }
+
+    /// Number of relays which sent all stored events for a subscription
+    pub fn eose(&self, id: &str) -> usize {
+        self.subs.get(id).map_or(0, |s| s.eose.len())
+    }
+
+    pub fn handle_eose(&mut self, sub: &str, relay: &str) {
+        if let Some(s) = self.subs.get_mut(sub) {
+            s.eose.insert(relay.to_string());
+        }
+    }
+
+    /// Handle an EVENT relay message, only messages of tracked subscriptions are parsed
+    pub fn handle_event(&mut self, sub: &str, msg: &str) {
+        let ids = match self.subs.get_mut(sub) {
+            Some(s) => &mut s.ids,
+            None => return,
+        };
+        let msg: serde_json::Value = match serde_json::from_str(msg) {
+            Ok(m) => m,
+            Err(_) => return,
+        };
+        let ev = if msg.is_array() { &msg[2] } else { &msg };
+        if let Some(id) = ev["id"]
+            .as_str()
+            .and_then(|i| hex::decode(i).ok())
+            .and_then(|i| i.try_into().ok())
+        {
+            ids.insert(id);
+        }
+    }
+}
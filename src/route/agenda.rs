@@ -0,0 +1,127 @@
+use crate::link::NostrLink;
+use crate::note_ref::NoteRef;
+use crate::route::{RouteServices, RouteType};
+use crate::stream_info::{StreamInfo, StreamStatus};
+use crate::sub::SubRef;
+use crate::theme::{NEUTRAL_500, NEUTRAL_800, ROUNDING_DEFAULT};
+use crate::widgets::{local_time, sub_or_poll, NostrWidget, Profile, ReminderButtons};
+use egui::{CursorIcon, Frame, Label, Margin, Response, RichText, ScrollArea, Sense, Ui};
+use itertools::Itertools;
+use nostrdb::{Filter, Note};
+use std::collections::HashSet;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// How far back stream events are loaded to find the planned ones
+const PLANNED_WINDOW_SECS: u64 = 30 * 24 * 60 * 60;
+
+/// Upcoming planned streams grouped by day
+pub struct AgendaPage {
+    events: HashSet<NoteRef>,
+    sub: Option<SubRef>,
+}
+
+impl AgendaPage {
+    pub fn new() -> Self {
+        Self {
+            events: HashSet::new(),
+            sub: None,
+        }
+    }
+
+    /// `status` isn't a single letter tag which relays can filter on, so every stream
+    /// updated within [PLANNED_WINDOW_SECS] is loaded instead of only the newest ones
+    fn get_filters() -> Vec<Filter> {
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_secs();
+        vec![Filter::new()
+            .kinds([30_311])
+            .since(now - PLANNED_WINDOW_SECS)
+            .build()]
+    }
+
+    fn render_row(ui: &mut Ui, services: &mut RouteServices<'_, '_>, event: &Note<'_>) {
+        let starts = local_time(event.starts());
+        Frame::none()
+            .fill(NEUTRAL_800)
+            .rounding(ROUNDING_DEFAULT)
+            .inner_margin(Margin::same(12.))
+            .show(ui, |ui| {
+                ui.set_width(ui.available_width());
+                ui.horizontal(|ui| {
+                    ui.label(RichText::new(starts.format("%H:%M").to_string()).size(18.));
+                    ui.vertical(|ui| {
+                        let title = ui
+                            .add(
+                                Label::new(
+                                    RichText::new(event.title().unwrap_or("Untitled")).size(16.),
+                                )
+                                .sense(Sense::click()),
+                            )
+                            .on_hover_cursor(CursorIcon::PointingHand);
+                        if title.clicked() {
+                            services.navigate(RouteType::EventPage {
+                                link: NostrLink::from_note(event),
+                                event: None,
+                            });
+                        }
+                        Profile::new(event.host()).size(24.).render(ui, services);
+                        ReminderButtons::new(event).render(ui, services);
+                    });
+                });
+            });
+    }
+}
+
+impl NostrWidget for AgendaPage {
+    fn render(&mut self, ui: &mut Ui, services: &mut RouteServices<'_, '_>) -> Response {
+        ScrollArea::vertical()
+            .show(ui, |ui| {
+                Frame::none()
+                    .inner_margin(Margin::same(12.))
+                    .show(ui, |ui| {
+                        ui.spacing_mut().item_spacing.y = 8.;
+                        ui.label(RichText::new("Agenda").size(32.));
+
+                        let events: Vec<Note> = self
+                            .events
+                            .iter()
+                            .filter_map(|e| {
+                                services.ctx.ndb.get_note_by_key(services.tx, e.key).ok()
+                            })
+                            .filter(|e| services.stream_status(e) == StreamStatus::Planned)
+                            .sorted_by_key(|e| e.starts())
+                            .collect();
+                        if events.is_empty() {
+                            ui.label(RichText::new("No upcoming streams").color(NEUTRAL_500));
+                        }
+                        for (day, events) in &events
+                            .iter()
+                            .chunk_by(|e| local_time(e.starts()).date_naive())
+                        {
+                            ui.add_space(8.);
+                            ui.label(
+                                RichText::new(day.format("%A, %e %B %Y").to_string()).size(20.),
+                            );
+                            for event in events {
+                                Self::render_row(ui, services, event);
+                            }
+                        }
+                    });
+                ui.response()
+            })
+            .inner
+    }
+
+    fn update(&mut self, services: &mut RouteServices<'_, '_>) -> anyhow::Result<()> {
+        sub_or_poll(
+            services.ctx.ndb,
+            services.tx,
+            services.ctx.pool,
+            &mut self.events,
+            &mut self.sub,
+            Self::get_filters(),
+        )
+    }
+}
@@ -1,17 +1,67 @@
+use crate::contacts::Contacts;
+use crate::link::NostrLink;
 use crate::note_ref::NoteRef;
+use crate::note_util::NoteUtil;
 use crate::note_view::NotesView;
-use crate::route::RouteServices;
+use crate::route::{RouteServices, RouteType};
+use crate::settings::StreamSort;
 use crate::stream_info::{StreamInfo, StreamStatus};
 use crate::sub::SubRef;
+use crate::theme::{MARGIN_DEFAULT, NEUTRAL_500};
 use crate::widgets;
-use crate::widgets::{sub_or_poll, NostrWidget};
-use egui::{Id, Response, RichText, ScrollArea, Ui};
+use crate::widgets::{sub_or_poll, NostrWidget, Pill};
+use crate::zap::Zap;
+use egui::{ComboBox, Frame, Id, Response, RichText, ScrollArea, Ui};
+use itertools::Itertools;
 use nostrdb::{Filter, Note};
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
+use std::time::{Duration, Instant};
+
+/// Number of tags in the popular tags row
+const POPULAR_TAGS: usize = 12;
+
+/// Number of older streams loaded when scrolling to the bottom
+const PAGE_SIZE: u64 = 50;
+
+/// Min time between replacing the zap subscription when more streams are loaded
+const ZAP_REFRESH: Duration = Duration::from_secs(30);
+
+/// Max time a page request stays open on the relays without EOSE
+const PAGE_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Older streams loaded while scrolling
+struct Page {
+    until: u64,
+    sub: Option<SubRef>,
+    /// Relay request of the page which is closed after EOSE
+    relay_sub: Option<(String, Instant)>,
+}
 
 pub struct HomePage {
     events: HashSet<NoteRef>,
     sub: Option<SubRef>,
+    /// Older streams loaded while scrolling
+    pages: Vec<Page>,
+    /// Bottom of the list is visible
+    load_more: bool,
+
+    contacts: Contacts,
+    /// Pubkeys the logged in account follows
+    follows: HashSet<[u8; 32]>,
+    /// Streams by or with followed pubkeys
+    following_events: HashSet<NoteRef>,
+    following_sub: Option<SubRef>,
+
+    /// Zap receipts of the loaded streams, only loaded when sorting by zaps
+    zaps: HashSet<NoteRef>,
+    zap_sub: Option<SubRef>,
+    /// Number of streams the zap subscription was made for
+    zap_streams: usize,
+    /// When the zap subscription was last replaced
+    zap_refreshed: Option<Instant>,
+    /// Zapped msats by stream tag value
+    zap_totals: HashMap<String, u64>,
+    zaps_counted: usize,
 }
 
 impl HomePage {
@@ -19,12 +69,160 @@ impl HomePage {
         Self {
             events: HashSet::new(),
             sub: None,
+            pages: vec![],
+            load_more: false,
+            contacts: Contacts::new(),
+            follows: HashSet::new(),
+            following_events: HashSet::new(),
+            following_sub: None,
+            zaps: HashSet::new(),
+            zap_sub: None,
+            zap_streams: 0,
+            zap_refreshed: None,
+            zap_totals: HashMap::new(),
+            zaps_counted: 0,
         }
     }
 
     fn get_filters() -> Vec<Filter> {
         vec![Filter::new().kinds([30_311, 30_313]).limit(100).build()]
     }
+
+    fn get_page_filters(until: u64) -> Vec<Filter> {
+        vec![Filter::new()
+            .kinds([30_311, 30_313])
+            .until(until)
+            .limit(PAGE_SIZE)
+            .build()]
+    }
+
+    fn get_following_filters(&self) -> Vec<Filter> {
+        vec![
+            Filter::new()
+                .kinds([30_311, 30_313])
+                .authors(&self.follows)
+                .limit(100)
+                .build(),
+            Filter::new()
+                .kinds([30_311, 30_313])
+                .pubkeys(&self.follows)
+                .limit(100)
+                .build(),
+        ]
+    }
+
+    /// Most used hashtags of the loaded streams
+    fn render_popular_tags(ui: &mut Ui, services: &mut RouteServices<'_, '_>, events: &[Note]) {
+        let tags: Vec<(String, usize)> = events
+            .iter()
+            .flat_map(|e| e.hashtags().into_iter().map(|t| t.to_lowercase()).unique())
+            .counts()
+            .into_iter()
+            .sorted_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(&b.0)))
+            .take(POPULAR_TAGS)
+            .collect();
+        if tags.is_empty() {
+            return;
+        }
+        Frame::none().inner_margin(MARGIN_DEFAULT).show(ui, |ui| {
+            ui.horizontal_wrapped(|ui| {
+                for (tag, count) in tags {
+                    if ui
+                        .add(Pill::new(&format!("#{} ({})", tag, count)).clickable())
+                        .clicked()
+                    {
+                        services.navigate(RouteType::TagPage { tag });
+                    }
+                }
+            });
+        });
+    }
+
+    fn render_sort(ui: &mut Ui, services: &mut RouteServices<'_, '_>) {
+        let label = |s: StreamSort| match s {
+            StreamSort::Starts => "Newest",
+            StreamSort::Viewers => "Viewers",
+            StreamSort::Zaps => "Zaps",
+        };
+        let mut sort = services.settings.stream_sort;
+        Frame::none().inner_margin(MARGIN_DEFAULT).show(ui, |ui| {
+            ui.horizontal(|ui| {
+                ui.label(RichText::new("Sort by").color(NEUTRAL_500));
+                ComboBox::from_id_salt("home-sort")
+                    .selected_text(label(sort))
+                    .show_ui(ui, |ui| {
+                        for s in [StreamSort::Starts, StreamSort::Viewers, StreamSort::Zaps] {
+                            ui.selectable_value(&mut sort, s, label(s));
+                        }
+                    });
+            });
+        });
+        if sort != services.settings.stream_sort {
+            services.settings.stream_sort = sort;
+            services.settings.save();
+        }
+    }
+
+    /// Load the zap receipts of all loaded streams and sum them up per stream
+    fn update_zaps(&mut self, services: &mut RouteServices<'_, '_>) -> anyhow::Result<()> {
+        let streams = self.events.union(&self.following_events).count();
+        if streams != self.zap_streams
+            && self
+                .zap_refreshed
+                .map_or(true, |t| t.elapsed() > ZAP_REFRESH)
+        {
+            self.zap_streams = streams;
+            self.zap_refreshed = Some(Instant::now());
+            if let Some(old) = self.zap_sub.take() {
+                services
+                    .ctx
+                    .pool
+                    .unsubscribe(format!("ndb-{}", old.sub.id()));
+            }
+        }
+        // filters are only used when subscribing
+        let filters = if self.zap_sub.is_none() {
+            let links: Vec<String> = self
+                .events
+                .union(&self.following_events)
+                .filter_map(|e| services.ctx.ndb.get_note_by_key(services.tx, e.key).ok())
+                .map(|n| NostrLink::from_note(&n).to_tag_value())
+                .collect();
+            if links.is_empty() {
+                return Ok(());
+            }
+            vec![Filter::new().kinds([9_735]).tags(links, 'a').build()]
+        } else {
+            vec![]
+        };
+        sub_or_poll(
+            services.ctx.ndb,
+            services.tx,
+            services.ctx.pool,
+            &mut self.zaps,
+            &mut self.zap_sub,
+            filters,
+        )?;
+
+        if self.zaps.len() != self.zaps_counted {
+            self.zaps_counted = self.zaps.len();
+            self.zap_totals.clear();
+            for z in self.zaps.iter() {
+                let note = match services.ctx.ndb.get_note_by_key(services.tx, z.key) {
+                    Ok(n) => n,
+                    Err(_) => continue,
+                };
+                let stream = match note.get_tag_value("a").and_then(|a| a.variant().str()) {
+                    Some(a) => a.to_string(),
+                    None => continue,
+                };
+                if let Ok(zap) = Zap::from_receipt(note) {
+                    *self.zap_totals.entry(stream).or_default() += zap.amount;
+                }
+            }
+        }
+        Ok(())
+    }
 }
 
 impl NostrWidget for HomePage {
@@ -33,15 +231,40 @@ impl NostrWidget for HomePage {
             .show(ui, |ui| {
                 let events: Vec<Note> = self
                     .events
-                    .iter()
+                    .union(&self.following_events)
                     .filter_map(|n| services.ctx.ndb.get_note_by_key(services.tx, n.key).ok())
-                    .filter(|e| e.can_play())
                     .collect();
 
+                Self::render_popular_tags(ui, services, &events);
+                Self::render_sort(ui, services);
+                let sort = services.settings.stream_sort;
+                let zaps = Some(&self.zap_totals);
+
+                let events_following = NotesView::from_vec(
+                    events
+                        .iter()
+                        .filter(|r| {
+                            matches!(
+                                services.stream_status(r),
+                                StreamStatus::Live | StreamStatus::Planned
+                            ) && r.involves(&self.follows)
+                        })
+                        .collect(),
+                );
+                if events_following.len() > 0 {
+                    widgets::StreamList::new(
+                        Id::new("following-streams"),
+                        events_following,
+                        Some(RichText::new("Following").size(32.0)),
+                    )
+                    .sort(sort, zaps)
+                    .render(ui, services);
+                }
+
                 let events_live = NotesView::from_vec(
                     events
                         .iter()
-                        .filter(|r| matches!(r.status(), StreamStatus::Live))
+                        .filter(|r| services.stream_status(r) == StreamStatus::Live)
                         .collect(),
                 );
                 if events_live.len() > 0 {
@@ -50,12 +273,14 @@ impl NostrWidget for HomePage {
                         events_live,
                         Some(RichText::new("Live").size(32.0)),
                     )
+                    .highlight(&self.follows)
+                    .sort(sort, zaps)
                     .render(ui, services);
                 }
                 let events_planned = NotesView::from_vec(
                     events
                         .iter()
-                        .filter(|r| matches!(r.status(), StreamStatus::Planned))
+                        .filter(|r| services.stream_status(r) == StreamStatus::Planned)
                         .collect(),
                 );
                 if events_planned.len() > 0 {
@@ -64,13 +289,15 @@ impl NostrWidget for HomePage {
                         events_planned,
                         Some(RichText::new("Planned").size(32.0)),
                     )
+                    .highlight(&self.follows)
+                    .sort(sort, zaps)
                     .render(ui, services);
                 }
                 let events_ended = NotesView::from_vec(
                     events
                         .iter()
                         .filter(|r| {
-                            matches!(r.status(), StreamStatus::Ended)
+                            services.stream_status(r) == StreamStatus::Ended
                                 && if let Some(r) = r.recording() {
                                     r.len() > 0
                                 } else {
@@ -85,14 +312,89 @@ impl NostrWidget for HomePage {
                         events_ended,
                         Some(RichText::new("Ended").size(32.0)),
                     )
+                    .highlight(&self.follows)
+                    .sort(sort, zaps)
                     .render(ui, services);
                 }
+
+                // infinite scroll, load older streams when the end of the list is visible
+                if !events.is_empty() {
+                    let end = Frame::none()
+                        .inner_margin(MARGIN_DEFAULT)
+                        .show(ui, |ui| {
+                            ui.label(RichText::new("Loading more..").color(NEUTRAL_500));
+                        })
+                        .response;
+                    self.load_more = ui.is_rect_visible(end.rect);
+                }
                 ui.response()
             })
             .inner
     }
 
     fn update(&mut self, services: &mut RouteServices<'_, '_>) -> anyhow::Result<()> {
+        self.contacts.update(services)?;
+        let follows = self.contacts.follows(services.ctx.ndb, services.tx);
+        if follows != self.follows {
+            self.follows = follows;
+            self.following_events.clear();
+            self.following_sub = None;
+        }
+        if !self.follows.is_empty() {
+            let filters = self.get_following_filters();
+            sub_or_poll(
+                services.ctx.ndb,
+                services.tx,
+                services.ctx.pool,
+                &mut self.following_events,
+                &mut self.following_sub,
+                filters,
+            )?;
+        }
+
+        // request the next page once the previous one brought in older streams
+        if self.load_more {
+            if let Some(oldest) = self.events.iter().map(|e| e.created_at).min() {
+                if self.pages.last().map_or(true, |p| oldest < p.until) {
+                    self.pages.push(Page {
+                        until: oldest,
+                        sub: None,
+                        relay_sub: None,
+                    });
+                }
+            }
+        }
+        for page in self.pages.iter_mut() {
+            let new = page.sub.is_none();
+            sub_or_poll(
+                services.ctx.ndb,
+                services.tx,
+                services.ctx.pool,
+                &mut self.events,
+                &mut page.sub,
+                Self::get_page_filters(page.until),
+            )?;
+            // pages don't get new streams, only keep the request open until the relays sent theirs
+            if let (true, Some(sub)) = (new, &page.sub) {
+                let id = format!("ndb-{}", sub.sub.id());
+                services.relay_results.watch(id.clone());
+                page.relay_sub = Some((id, Instant::now()));
+                services.egui.request_repaint_after(PAGE_TIMEOUT);
+            }
+            let done = page.relay_sub.as_ref().map_or(false, |(id, opened)| {
+                services.relay_results.eose(id) >= services.ctx.pool.relays.len()
+                    || opened.elapsed() > PAGE_TIMEOUT
+            });
+            if done {
+                if let Some((id, _)) = page.relay_sub.take() {
+                    services.relay_results.unsubscribe(services.ctx.pool, &id);
+                }
+            }
+        }
+
+        if services.settings.stream_sort == StreamSort::Zaps {
+            self.update_zaps(services)?;
+        }
         sub_or_poll(
             services.ctx.ndb,
             services.tx,
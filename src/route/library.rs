@@ -0,0 +1,122 @@
+use crate::route::RouteServices;
+use crate::services::download::DownloadState;
+use crate::theme::{FONT_SIZE, NEUTRAL_500, NEUTRAL_800, ROUNDING_DEFAULT};
+use crate::widgets::{Button, NostrWidget, StreamPlayer};
+use egui::{
+    vec2, Align, Color32, Frame, Layout, Margin, ProgressBar, Response, RichText, ScrollArea, Ui,
+};
+
+/// Downloaded recordings which can be played offline
+pub struct LibraryPage {
+    /// File name and player of the recording which is playing
+    playing: Option<(String, StreamPlayer)>,
+}
+
+impl LibraryPage {
+    pub fn new() -> Self {
+        Self { playing: None }
+    }
+}
+
+impl NostrWidget for LibraryPage {
+    fn render(&mut self, ui: &mut Ui, services: &mut RouteServices<'_, '_>) -> Response {
+        ScrollArea::vertical()
+            .show(ui, |ui| {
+                Frame::none()
+                    .inner_margin(Margin::same(12.))
+                    .show(ui, |ui| {
+                        ui.spacing_mut().item_spacing.y = 8.;
+                        if let Some((_, player)) = &mut self.playing {
+                            let w = ui.available_width();
+                            ui.add_sized(vec2(w, (w / 16.0 * 9.0).min(600.0)), player);
+                        }
+
+                        ui.label(RichText::new("Library").size(32.));
+                        for d in services.downloads.active() {
+                            Frame::none()
+                                .fill(NEUTRAL_800)
+                                .rounding(ROUNDING_DEFAULT)
+                                .inner_margin(Margin::same(12.))
+                                .show(ui, |ui| {
+                                    ui.label(&d.entry.title);
+                                    match d.state() {
+                                        DownloadState::Downloading(p) => {
+                                            ui.add(ProgressBar::new(p).show_percentage());
+                                        }
+                                        DownloadState::Remuxing => {
+                                            ui.label("Saving..");
+                                        }
+                                        DownloadState::Failed(e) => {
+                                            ui.label(RichText::new(e).color(Color32::RED));
+                                        }
+                                        DownloadState::Done => {}
+                                    }
+                                });
+                        }
+
+                        if services.downloads.library().is_empty()
+                            && services.downloads.active().is_empty()
+                        {
+                            ui.label(
+                                RichText::new("Downloaded recordings show up here")
+                                    .color(NEUTRAL_500),
+                            );
+                        }
+
+                        let mut play = None;
+                        let mut delete = None;
+                        for e in services.downloads.library() {
+                            Frame::none()
+                                .fill(NEUTRAL_800)
+                                .rounding(ROUNDING_DEFAULT)
+                                .inner_margin(Margin::same(12.))
+                                .show(ui, |ui| {
+                                    ui.horizontal(|ui| {
+                                        ui.vertical(|ui| {
+                                            ui.label(&e.title);
+                                            ui.label(
+                                                RichText::new(format!(
+                                                    "{:.1} MB",
+                                                    e.size as f64 / 1_000_000.0
+                                                ))
+                                                .size(FONT_SIZE)
+                                                .color(NEUTRAL_500),
+                                            );
+                                        });
+                                        ui.with_layout(
+                                            Layout::right_to_left(Align::Center),
+                                            |ui| {
+                                                if Button::new().text(ui, "Delete").clicked() {
+                                                    delete = Some(e.file.clone());
+                                                }
+                                                if Button::new().text(ui, "Play").clicked() {
+                                                    play = Some(e.clone());
+                                                }
+                                            },
+                                        );
+                                    });
+                                });
+                        }
+
+                        if let Some(e) = play {
+                            let path = services.downloads.path(&e);
+                            let player =
+                                StreamPlayer::new(ui.ctx(), &path.to_string_lossy().to_string());
+                            self.playing = Some((e.file, player));
+                        }
+                        if let Some(file) = delete {
+                            if self.playing.as_ref().map_or(false, |(f, _)| f == &file) {
+                                self.playing = None;
+                            }
+                            services.downloads.remove(&file);
+                        }
+                    });
+                ui.response()
+            })
+            .inner
+    }
+
+    fn update(&mut self, _services: &mut RouteServices<'_, '_>) -> anyhow::Result<()> {
+        Ok(())
+    }
+}
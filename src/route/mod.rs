@@ -1,35 +1,56 @@
 use crate::link::NostrLink;
+use crate::nip05;
+use crate::nip05::{Nip05Badge, Nip05Cache};
+use crate::outbox::{Outbox, PublishState};
+use crate::player_manager::PlayerManager;
+use crate::relay_results::RelayResults;
+use crate::services::download::Downloads;
 use crate::services::ffmpeg_loader::FfmpegLoader;
+use crate::services::reminders::Reminders;
+use crate::settings::Settings;
+use crate::stream_info::{StreamInfo, StreamStatus};
 use crate::widgets::PlaceholderRect;
 use anyhow::{anyhow, bail};
 use egui::load::SizedTexture;
 use egui::{vec2, Context, Id, Image, ImageSource, TextureHandle, Ui, Vec2};
 use egui_video::ffmpeg_rs_raw::Transcoder;
 use ehttp::Response;
-use enostr::EventClientMessage;
 use lnurl::lightning_address::LightningAddress;
 use lnurl::pay::PayResponse;
 use lnurl::LnUrlResponse;
 use log::{info, warn};
 use nostr::{serde_json, Event, EventBuilder, JsonUtil, Keys, Kind, SecretKey, Tag};
-use nostrdb::{NdbProfile, NoteKey, Transaction};
+use nostrdb::{NdbProfile, Note, NoteKey, Transaction};
 use notedeck::{AppContext, ImageCache};
 use poll_promise::Promise;
 use std::collections::HashMap;
 use std::path::Path;
 use std::sync::mpsc;
 use std::task::Poll;
+use std::time::{SystemTime, UNIX_EPOCH};
 
+mod agenda;
 mod home;
+mod library;
 mod login;
+mod overlay;
 mod profile;
+mod search;
+mod settings;
 mod stream;
+mod tag;
 
 pub mod page {
+    pub use super::agenda::AgendaPage;
     pub use super::home::HomePage;
+    pub use super::library::LibraryPage;
     pub use super::login::LoginPage;
+    pub use super::overlay::OverlayPage;
     pub use super::profile::ProfilePage;
+    pub use super::search::SearchPage;
+    pub use super::settings::SettingsPage;
     pub use super::stream::StreamPage;
+    pub use super::tag::TagPage;
 }
 
 #[derive(PartialEq)]
@@ -43,6 +64,13 @@ pub enum RouteType {
         link: NostrLink,
     },
     LoginPage,
+    LibraryPage,
+    SearchPage,
+    SettingsPage,
+    AgendaPage,
+    TagPage {
+        tag: String,
+    },
 
     // special kind for modifying route state
     Action(RouteAction),
@@ -53,13 +81,47 @@ pub enum RouteAction {
     DemandProfile([u8; 32]),
 }
 
+/// State owned by the app which is shared with every page
+pub struct AppServices {
+    pub outbox: Outbox,
+    pub settings: Settings,
+    pub players: PlayerManager,
+    pub downloads: Downloads,
+    pub reminders: Reminders,
+    pub nip05: Nip05Cache,
+    pub relay_results: RelayResults,
+}
+
+impl AppServices {
+    pub fn new(data_path: &Path) -> Self {
+        Self {
+            outbox: Outbox::new(),
+            settings: Settings::load(data_path),
+            players: PlayerManager::new(),
+            downloads: Downloads::new(data_path),
+            reminders: Reminders::new(data_path),
+            nip05: Nip05Cache::new(),
+            relay_results: RelayResults::new(),
+        }
+    }
+}
+
 pub struct RouteServices<'a, 'ctx> {
     pub egui: Context,
     pub tx: &'a Transaction,
     pub ctx: &'a mut AppContext<'ctx>,
+    pub settings: &'a mut Settings,
+    /// Stream player which keeps playing while browsing other pages
+    pub players: &'a mut PlayerManager,
+    pub downloads: &'a mut Downloads,
+    pub reminders: &'a mut Reminders,
+    /// Events relays sent for searches
+    pub relay_results: &'a mut RelayResults,
 
     router: mpsc::Sender<RouteType>,
     fetch: &'a mut HashMap<String, Promise<ehttp::Result<Response>>>,
+    outbox: &'a mut Outbox,
+    nip05: &'a mut Nip05Cache,
 }
 
 impl<'a, 'ctx> RouteServices<'a, 'ctx> {
@@ -69,13 +131,30 @@ impl<'a, 'ctx> RouteServices<'a, 'ctx> {
         ctx: &'a mut AppContext<'ctx>,
         router: mpsc::Sender<RouteType>,
         fetch: &'a mut HashMap<String, Promise<ehttp::Result<Response>>>,
+        app: &'a mut AppServices,
     ) -> Self {
+        let AppServices {
+            outbox,
+            settings,
+            players,
+            downloads,
+            reminders,
+            nip05,
+            relay_results,
+        } = app;
         Self {
             egui,
             tx,
             ctx,
+            settings,
+            players,
+            downloads,
+            reminders,
+            relay_results,
             router,
             fetch,
+            outbox,
+            nip05,
         }
     }
 
@@ -96,11 +175,22 @@ impl<'a, 'ctx> RouteServices<'a, 'ctx> {
         if let Err(e) = self.ctx.ndb.process_event(&ev_json) {
             warn!("Failed to submit event {}", e);
         }
-        self.ctx
-            .pool
-            .send(&enostr::ClientMessage::Event(EventClientMessage {
-                note_json: ev_json,
-            }));
+        self.outbox
+            .publish(self.ctx.pool, event.id.to_bytes(), ev_json);
+    }
+
+    /// Relay acknowledgement state of an event we published
+    pub fn publish_state(&self, id: &[u8; 32]) -> Option<PublishState> {
+        self.outbox.state(id)
+    }
+
+    /// Stream status with the stale live window from the settings
+    pub fn stream_status(&self, note: &Note<'_>) -> StreamStatus {
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_secs();
+        note.status_at(now, self.settings.stale_live_minutes * 60)
     }
 
     /// Load/Fetch profiles
@@ -155,20 +245,86 @@ impl<'a, 'ctx> RouteServices<'a, 'ctx> {
         }
     }
 
-    pub fn write_live_chat_msg(&self, link: &NostrLink, msg: &str) -> Option<Event> {
-        if msg.is_empty() {
-            return None;
+    /// NIP-05 identifier of the profile resolves to its pubkey, `None` while loading
+    pub fn nip05_verified(&mut self, pubkey: &[u8; 32], nip05: &str) -> Option<bool> {
+        if let Some(v) = self.nip05.get(pubkey, nip05) {
+            return Some(v);
+        }
+        let (name, domain) = match nip05::parse(nip05) {
+            Some(n) => n,
+            None => return Some(false),
+        };
+        let url = nip05::url(&name, &domain);
+        let verified = match self.fetch(&url) {
+            Poll::Ready(Ok(r)) => r.ok && nip05::verify(&r.bytes, &name, pubkey),
+            Poll::Ready(Err(_)) => false,
+            Poll::Pending => return None,
+        };
+        // fetch again once the cached result expires
+        self.fetch.remove(&url);
+        let profile_name = self.profile(pubkey).and_then(|p| p.name());
+        self.nip05.insert(pubkey, nip05, verified, profile_name);
+        Some(verified)
+    }
+
+    /// Verified badge, or a warning when the name copies a verified user
+    pub fn nip05_badge(
+        &mut self,
+        pubkey: &[u8; 32],
+        profile: &Option<NdbProfile<'_>>,
+    ) -> Nip05Badge {
+        if let Some(nip05) = profile.and_then(|p| p.nip05()) {
+            if self.nip05_verified(pubkey, nip05) == Some(true) {
+                if let Some((_, domain)) = nip05::parse(nip05) {
+                    return Nip05Badge::Verified(domain);
+                }
+            }
         }
-        if let Some(key) = self.current_account_keys() {
-            EventBuilder::new(Kind::LiveEventMessage, msg)
-                .tag(Tag::parse(link.to_tag()).unwrap())
-                .sign_with_keys(&key)
-                .ok()
-        } else {
-            None
+        match profile.and_then(|p| p.name()) {
+            Some(name) if self.nip05.is_lookalike(pubkey, name) => Nip05Badge::Lookalike,
+            _ => Nip05Badge::None,
         }
     }
 
+    pub fn write_live_chat_msg(
+        &self,
+        link: &NostrLink,
+        msg: &str,
+        tags: Vec<Tag>,
+    ) -> anyhow::Result<Event> {
+        if msg.is_empty() {
+            bail!("Message is empty");
+        }
+        let key = self
+            .current_account_keys()
+            .ok_or(anyhow!("Login to chat"))?;
+        Ok(EventBuilder::new(Kind::LiveEventMessage, msg)
+            .tag(Tag::parse(link.to_tag())?)
+            .tags(tags)
+            .sign_with_keys(&key)?)
+    }
+
+    /// Live stream clip (kind 1313) of an uploaded video
+    pub fn write_live_clip(
+        &self,
+        link: &NostrLink,
+        url: &str,
+        title: &str,
+    ) -> anyhow::Result<Event> {
+        let key = self
+            .current_account_keys()
+            .ok_or(anyhow!("Login to publish clips"))?;
+        Ok(EventBuilder::new(Kind::Custom(1_313), title)
+            .tag(Tag::parse(link.to_tag())?)
+            .tag(Tag::parse(vec!["r".to_string(), url.to_string()])?)
+            .tag(Tag::parse(vec!["title".to_string(), title.to_string()])?)
+            .tag(Tag::parse(vec![
+                "alt".to_string(),
+                format!("Live stream clip: {}", url),
+            ])?)
+            .sign_with_keys(&key)?)
+    }
+
     pub fn current_account_keys(&self) -> Option<Keys> {
         self.ctx
             .accounts
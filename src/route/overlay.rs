@@ -0,0 +1,171 @@
+use crate::note_ref::NoteRef;
+use crate::overlay::OverlayConfig;
+use crate::route::RouteServices;
+use crate::settings::ContentWarnings;
+use crate::stream_info::StreamInfo;
+use crate::sub::SubRef;
+use crate::widgets::{sub_or_poll, Chat, ChatMessage, ChatZap, NostrWidget};
+use crate::zap::Zap;
+use egui::{Align, Align2, Area, Frame, Id, Layout, Margin, Response, Ui};
+use itertools::Itertools;
+use nostrdb::{Filter, Note, NoteKey};
+use std::borrow::Borrow;
+use std::collections::HashSet;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// How long the zap alert grows before reaching full size
+const ZAP_ANIMATION_SECS: f64 = 0.4;
+
+/// Chat only view for capturing in OBS
+pub struct OverlayPage {
+    config: OverlayConfig,
+    chat: Option<Chat>,
+
+    events: HashSet<NoteRef>,
+    sub: Option<SubRef>,
+}
+
+impl OverlayPage {
+    pub fn new(config: OverlayConfig) -> Self {
+        Self {
+            config,
+            chat: None,
+            events: HashSet::new(),
+            sub: None,
+        }
+    }
+
+    fn get_filters(&self) -> Vec<Filter> {
+        let f: Filter = self.config.link.borrow().try_into().unwrap();
+        vec![f.limit_mut(1)]
+    }
+
+    /// Opacity of an event of this age, fading out during the last second
+    fn opacity(&self, age: f64) -> f32 {
+        if self.config.fade_secs == 0 {
+            return 1.0;
+        }
+        let remaining = self.config.fade_secs as f64 - age;
+        remaining.clamp(0.0, 1.0) as f32
+    }
+
+    fn render_zap_alert(
+        &self,
+        ui: &mut Ui,
+        services: &mut RouteServices<'_, '_>,
+        key: NoteKey,
+        age: f64,
+    ) {
+        let zap = match services
+            .ctx
+            .ndb
+            .get_note_by_key(services.tx, key)
+            .map(Zap::from_receipt)
+        {
+            Ok(Ok(z)) => z,
+            _ => return,
+        };
+        let grow = (age / ZAP_ANIMATION_SECS).min(1.0) as f32;
+        let font_size = self.config.font_size * 1.5 * (0.5 + 0.5 * grow);
+        let profile = services.profile(&zap.sender);
+
+        Area::new(Id::new("overlay-zap-alert"))
+            .anchor(Align2::CENTER_TOP, [0.0, 40.0])
+            .show(ui.ctx(), |ui| {
+                ui.set_opacity(self.opacity(age) * grow);
+                ui.set_max_width(ui.ctx().screen_rect().width() * 0.8);
+                ChatZap::new(&zap, &profile)
+                    .font_size(font_size)
+                    .render(ui, services.ctx.img_cache);
+            });
+    }
+}
+
+impl NostrWidget for OverlayPage {
+    fn render(&mut self, ui: &mut Ui, services: &mut RouteServices<'_, '_>) -> Response {
+        let stream = if let Some(s) = self
+            .events
+            .iter()
+            .next()
+            .and_then(|e| services.ctx.ndb.get_note_by_key(services.tx, e.key).ok())
+        {
+            s
+        } else {
+            return ui.response();
+        };
+        let chat = self
+            .chat
+            .get_or_insert_with(|| Chat::new(self.config.link.clone(), stream.key().unwrap()));
+
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_secs_f64();
+        let visible: Vec<(Note, f64)> = chat
+            .events()
+            .sorted_by(|a, b| b.created_at.cmp(&a.created_at))
+            .map(|e| (e, now - e.created_at as f64))
+            .take_while(|(_, age)| {
+                self.config.fade_secs == 0 || *age < self.config.fade_secs as f64
+            })
+            .filter_map(|(e, age)| {
+                services
+                    .ctx
+                    .ndb
+                    .get_note_by_key(services.tx, e.key)
+                    .ok()
+                    .map(|n| (n, age))
+            })
+            .take(50)
+            .collect();
+
+        // keep animating / fading
+        ui.ctx().request_repaint_after(Duration::from_millis(50));
+
+        if self.config.show_zaps {
+            if let Some((ev, age)) = visible.iter().find(|(e, _)| e.kind() == 9735) {
+                self.render_zap_alert(ui, services, ev.key().unwrap(), *age);
+            }
+        }
+
+        Frame::none()
+            .inner_margin(Margin::same(12.))
+            .show(ui, |ui| {
+                ui.with_layout(Layout::bottom_up(Align::Min), |ui| {
+                    ui.spacing_mut().item_spacing.y = 8.0;
+                    if !self.config.show_chat {
+                        return;
+                    }
+                    let hide_cw = services.settings.content_warnings == ContentWarnings::AlwaysHide;
+                    for (ev, age) in visible.iter().filter(|(e, _)| {
+                        e.kind() == 1311 && !(hide_cw && e.content_warning().is_some())
+                    }) {
+                        let profile = services.profile(ev.pubkey());
+                        ui.scope(|ui| {
+                            ui.set_opacity(self.opacity(*age));
+                            ChatMessage::new(&stream, ev, &profile)
+                                .font_size(self.config.font_size)
+                                .render(ui, services);
+                        });
+                    }
+                });
+            })
+            .response
+    }
+
+    fn update(&mut self, services: &mut RouteServices<'_, '_>) -> anyhow::Result<()> {
+        let filters = self.get_filters();
+        sub_or_poll(
+            services.ctx.ndb,
+            services.tx,
+            services.ctx.pool,
+            &mut self.events,
+            &mut self.sub,
+            filters,
+        )?;
+        if let Some(c) = self.chat.as_mut() {
+            c.update(services)?;
+        }
+        Ok(())
+    }
+}
@@ -1,17 +1,32 @@
+use crate::contacts::Contacts;
+use crate::nip05;
 use crate::note_ref::NoteRef;
 use crate::note_view::NotesView;
 use crate::route::{image_from_cache, RouteServices};
+use crate::stream_info::{StreamInfo, StreamStatus};
 use crate::sub::SubRef;
-use crate::theme::{MARGIN_DEFAULT, ROUNDING_DEFAULT};
-use crate::widgets::{sub_or_poll, NostrWidget, PlaceholderRect, Profile, StreamList};
-use egui::{vec2, Frame, Id, Response, ScrollArea, Ui, Widget};
+use crate::theme::{MARGIN_DEFAULT, NEUTRAL_500, PRIMARY, ROUNDING_DEFAULT};
+use crate::widgets::{sub_or_poll, Button, NostrWidget, PlaceholderRect, Profile, StreamList};
+use egui::{vec2, Color32, Frame, Id, Response, RichText, ScrollArea, Stroke, Ui, Widget};
+use log::warn;
 use nostrdb::{Filter, Note};
 use std::collections::HashSet;
 
+/// Max number of contact lists loaded to count followers
+const FOLLOWERS_LIMIT: u64 = 1000;
+
 pub struct ProfilePage {
     pubkey: [u8; 32],
     events: HashSet<NoteRef>,
     sub: Option<SubRef>,
+
+    /// Contact list of this profile, for the following count
+    contacts: Contacts,
+    /// Contact list of the logged in account, for following this profile
+    account_contacts: Contacts,
+    /// Contact lists which include this profile
+    followers: HashSet<NoteRef>,
+    followers_sub: Option<SubRef>,
 }
 
 impl ProfilePage {
@@ -20,8 +35,117 @@ impl ProfilePage {
             pubkey,
             events: HashSet::new(),
             sub: None,
+            contacts: Contacts::of(pubkey),
+            account_contacts: Contacts::new(),
+            followers: HashSet::new(),
+            followers_sub: None,
+        }
+    }
+
+    /// Number of pubkeys with a contact list containing this profile, from at most
+    /// [FOLLOWERS_LIMIT] contact lists
+    fn follower_count(&self, services: &RouteServices<'_, '_>) -> usize {
+        self.followers
+            .iter()
+            .filter_map(|e| services.ctx.ndb.get_note_by_key(services.tx, e.key).ok())
+            .map(|n| *n.pubkey())
+            .collect::<HashSet<_>>()
+            .len()
+    }
+
+    fn render_follow(&self, ui: &mut Ui, services: &mut RouteServices<'_, '_>) {
+        let account = match services.ctx.accounts.get_selected_account() {
+            Some(a) => *a.pubkey.bytes(),
+            None => return,
+        };
+        if account == self.pubkey {
+            return;
+        }
+        let following = self
+            .account_contacts
+            .follows(services.ctx.ndb, services.tx)
+            .contains(&self.pubkey);
+        let loaded = self.account_contacts.loaded();
+        let rsp = if following {
+            Button::new().disabled(!loaded).text(ui, "Unfollow")
+        } else {
+            Button::new()
+                .with_color(PRIMARY)
+                .disabled(!loaded)
+                .text(ui, "Follow")
+        };
+        // starting a new contact list replaces one which didn't load, so ask first
+        let confirm_id = Id::new(("follow-new-list", self.pubkey));
+        let mut create = false;
+        if rsp.clicked() && loaded {
+            if self.account_contacts.found() {
+                self.follow(services, !following, false);
+            } else {
+                ui.ctx().data_mut(|d| d.insert_temp(confirm_id, true));
+            }
+        }
+        if ui.ctx().data(|d| d.get_temp(confirm_id).unwrap_or(false)) {
+            ui.label(RichText::new("No contact list found, start a new one?").color(NEUTRAL_500));
+            if Button::new()
+                .with_color(PRIMARY)
+                .text(ui, "Start new")
+                .clicked()
+            {
+                create = true;
+                ui.ctx().data_mut(|d| d.remove::<bool>(confirm_id));
+            }
+            if Button::new().text(ui, "Cancel").clicked() {
+                ui.ctx().data_mut(|d| d.remove::<bool>(confirm_id));
+            }
+        }
+        if create {
+            self.follow(services, true, true);
         }
     }
+
+    fn follow(&self, services: &mut RouteServices<'_, '_>, follow: bool, create: bool) {
+        if let Err(e) = self
+            .account_contacts
+            .set_follow(services, &self.pubkey, follow, create)
+        {
+            warn!("Failed to update contact list: {}", e);
+        }
+    }
+
+    fn render_nip05(&self, ui: &mut Ui, services: &mut RouteServices<'_, '_>, nip05: &str) {
+        let (icon, color, hint) = match services.nip05_verified(&self.pubkey, nip05) {
+            Some(true) => ("✔", PRIMARY, "Verified"),
+            Some(false) => ("✖", Color32::RED, "Could not be verified"),
+            None => ("…", NEUTRAL_500, "Verifying"),
+        };
+        ui.horizontal(|ui| {
+            ui.label(RichText::new(icon).color(color));
+            ui.label(RichText::new(nip05::display(nip05)).color(NEUTRAL_500));
+        })
+        .response
+        .on_hover_text(hint);
+    }
+
+    /// Big card linking to the stream the profile is live in
+    fn render_live(&self, ui: &mut Ui, services: &mut RouteServices<'_, '_>, live: &[&Note]) {
+        if live.is_empty() {
+            return;
+        }
+        Frame::none().inner_margin(MARGIN_DEFAULT).show(ui, |ui| {
+            Frame::none()
+                .stroke(Stroke::new(2.0, PRIMARY))
+                .rounding(ROUNDING_DEFAULT)
+                .inner_margin(MARGIN_DEFAULT)
+                .show(ui, |ui| {
+                    StreamList::new(
+                        Id::new("profile-live"),
+                        NotesView::from_vec(live.to_vec()),
+                        Some(RichText::new("🔴 Live now").size(24.).color(PRIMARY)),
+                    )
+                    .render(ui, services);
+                });
+        });
+    }
 }
 
 impl NostrWidget for ProfilePage {
@@ -48,21 +172,61 @@ impl NostrWidget for ProfilePage {
                     } else {
                         ui.add(PlaceholderRect);
                     }
-                    Profile::from_profile(&self.pubkey, &profile)
-                        .size(88.0)
-                        .render(ui, services);
+                    ui.horizontal(|ui| {
+                        Profile::from_profile(&self.pubkey, &profile)
+                            .size(88.0)
+                            .render(ui, services);
+                        self.render_follow(ui, services);
+                    });
+                    if let Some(nip05) = profile.and_then(|p| p.nip05()) {
+                        self.render_nip05(ui, services, nip05);
+                    }
+                    let following = self.contacts.follows(services.ctx.ndb, services.tx).len();
+                    let followers = self.follower_count(services);
+                    ui.label(
+                        RichText::new(format!(
+                            "{} following · ~{} followers",
+                            following, followers
+                        ))
+                        .color(NEUTRAL_500),
+                    )
+                    .on_hover_text(format!(
+                        "Followers are counted from up to {} contact lists found on your relays",
+                        FOLLOWERS_LIMIT
+                    ));
                 });
 
-            let events: Vec<Note> = self
+            let events: Vec<(Note, StreamStatus)> = self
                 .events
                 .iter()
                 .filter_map(|e| services.ctx.ndb.get_note_by_key(services.tx, e.key).ok())
+                .map(|n| {
+                    let status = services.stream_status(&n);
+                    (n, status)
+                })
                 .collect();
+            let with_status = |status: StreamStatus| {
+                events
+                    .iter()
+                    .filter(|(_, s)| *s == status)
+                    .map(|(e, _)| e)
+                    .collect::<Vec<&Note>>()
+            };
 
+            self.render_live(ui, services, &with_status(StreamStatus::Live));
+            let upcoming = with_status(StreamStatus::Planned);
+            if !upcoming.is_empty() {
+                StreamList::new(
+                    Id::new("profile-upcoming"),
+                    NotesView::from_vec(upcoming),
+                    Some(RichText::new("Upcoming").size(24.)),
+                )
+                .render(ui, services);
+            }
             StreamList::new(
-                Id::from("profile-streams"),
-                NotesView::from_vec(events.iter().collect()),
-                Some("Past Streams"),
+                Id::new("profile-streams"),
+                NotesView::from_vec(with_status(StreamStatus::Ended)),
+                Some(RichText::new("Past Streams").size(24.)),
             )
             .render(ui, services);
         });
@@ -70,6 +234,20 @@ impl NostrWidget for ProfilePage {
     }
 
     fn update(&mut self, services: &mut RouteServices<'_, '_>) -> anyhow::Result<()> {
+        self.contacts.update(services)?;
+        self.account_contacts.update(services)?;
+        sub_or_poll(
+            services.ctx.ndb,
+            services.tx,
+            services.ctx.pool,
+            &mut self.followers,
+            &mut self.followers_sub,
+            vec![Filter::new()
+                .kinds([3])
+                .pubkeys(&[self.pubkey])
+                .limit(FOLLOWERS_LIMIT)
+                .build()],
+        )?;
         sub_or_poll(
             services.ctx.ndb,
             services.tx,
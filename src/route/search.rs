@@ -0,0 +1,258 @@
+use crate::link::NostrLink;
+use crate::note_ref::NoteRef;
+use crate::note_view::NotesView;
+use crate::route::{RouteServices, RouteType};
+use crate::stream_info::StreamInfo;
+use crate::theme::NEUTRAL_500;
+use crate::widgets::{Button, NativeTextInput, NostrWidget, Profile, StreamList};
+use egui::{CursorIcon, Frame, Id, Key, Margin, Response, RichText, ScrollArea, Sense, Ui};
+use itertools::Itertools;
+use nostrdb::{Filter, Ndb, Note, Transaction};
+use std::collections::HashSet;
+
+/// Max number of profiles shown in the results
+const MAX_PROFILES: usize = 20;
+
+/// Max number of streams found locally
+const MAX_STREAMS: u64 = 50;
+
+/// Number of recent local streams matched by title and summary, which the
+/// fulltext index doesn't cover
+const MAX_LOCAL_SCAN: i32 = 1000;
+
+/// Search streams by title, summary and hashtag, and profiles by name.
+///
+/// Relays are asked for NIP-50 matches, relays without NIP-50 ignore the search
+/// and send anything, so their results are matched again before they're shown.
+pub struct SearchPage {
+    input: String,
+    /// Lowercase search term which results are shown for
+    query: String,
+    /// Local stream matches
+    streams: HashSet<NoteRef>,
+    /// Local profile name matches
+    profiles: Vec<[u8; 32]>,
+    /// Local database was searched for the current query
+    searched: bool,
+    /// Relay subscription id of the current query
+    relay_sub: Option<String>,
+}
+
+impl SearchPage {
+    pub fn new() -> Self {
+        Self {
+            input: String::new(),
+            query: String::new(),
+            streams: HashSet::new(),
+            profiles: vec![],
+            searched: false,
+            relay_sub: None,
+        }
+    }
+
+    fn submit(&mut self, services: &mut RouteServices<'_, '_>) {
+        let query = self.input.trim().to_lowercase();
+        if query == self.query {
+            return;
+        }
+        if let Some(id) = self.relay_sub.take() {
+            services.relay_results.unsubscribe(services.ctx.pool, &id);
+        }
+        self.query = query;
+        self.streams.clear();
+        self.profiles.clear();
+        self.searched = false;
+    }
+
+    fn stream_filters(&self) -> Vec<Filter> {
+        let tag = self.query.trim_start_matches('#');
+        vec![
+            Filter::new()
+                .kinds([30_311, 30_313])
+                .search(&self.query)
+                .limit(MAX_STREAMS)
+                .build(),
+            Filter::new()
+                .kinds([30_311, 30_313])
+                .tags([tag.to_string()], 't')
+                .limit(MAX_STREAMS)
+                .build(),
+        ]
+    }
+
+    fn relay_filters(&self) -> Vec<Filter> {
+        let mut filters = self.stream_filters();
+        filters.push(
+            Filter::new()
+                .kinds([0])
+                .search(&self.query)
+                .limit(MAX_PROFILES as u64)
+                .build(),
+        );
+        filters
+    }
+
+    fn matches_stream(&self, note: &Note<'_>) -> bool {
+        let tag = self.query.trim_start_matches('#');
+        let text = |s: Option<&str>| s.map_or(false, |s| s.to_lowercase().contains(&self.query));
+        text(note.title())
+            || text(note.summary())
+            || note.hashtags().iter().any(|t| t.eq_ignore_ascii_case(tag))
+    }
+
+    fn matches_profile(&self, services: &RouteServices<'_, '_>, pubkey: &[u8; 32]) -> bool {
+        services.profile(pubkey).map_or(false, |p| {
+            [p.name(), p.display_name()]
+                .iter()
+                .flatten()
+                .any(|n| n.to_lowercase().contains(&self.query))
+        })
+    }
+
+    /// Query the local database, text search, the hashtag index and the
+    /// title and summary of recent streams
+    fn search_local(&mut self, ndb: &Ndb, tx: &Transaction) -> anyhow::Result<()> {
+        for r in ndb.query(tx, self.stream_filters().as_slice(), MAX_STREAMS as i32)? {
+            self.streams.insert(NoteRef::from_query_result(r));
+        }
+        let recent = Filter::new().kinds([30_311, 30_313]).build();
+        for r in ndb.query(tx, &[recent], MAX_LOCAL_SCAN)? {
+            if self.matches_stream(&r.note) {
+                self.streams.insert(NoteRef::from_query_result(r));
+            }
+        }
+        self.profiles = ndb
+            .search_profile(tx, &self.query, MAX_PROFILES as u32)?
+            .into_iter()
+            .copied()
+            .collect();
+        Ok(())
+    }
+
+    /// Notes which relays sent for the current query and match it
+    fn relay_notes<'a>(&self, services: &RouteServices<'a, '_>) -> Vec<Note<'a>> {
+        let ids = match self
+            .relay_sub
+            .as_ref()
+            .and_then(|s| services.relay_results.get(s))
+        {
+            Some(i) => i,
+            None => return vec![],
+        };
+        ids.iter()
+            .filter_map(|id| services.ctx.ndb.get_note_by_id(services.tx, id).ok())
+            .filter(|n| {
+                if n.kind() == 0 {
+                    self.matches_profile(services, n.pubkey())
+                } else {
+                    self.matches_stream(n)
+                }
+            })
+            .collect()
+    }
+}
+
+impl NostrWidget for SearchPage {
+    fn render(&mut self, ui: &mut Ui, services: &mut RouteServices<'_, '_>) -> Response {
+        ScrollArea::vertical()
+            .show(ui, |ui| {
+                Frame::none()
+                    .inner_margin(Margin::same(12.))
+                    .show(ui, |ui| {
+                        ui.spacing_mut().item_spacing.y = 8.;
+                        ui.label(RichText::new("Search").size(32.));
+                        ui.horizontal(|ui| {
+                            let input = ui.add_sized(
+                                [ui.available_width() - 100., 24.],
+                                NativeTextInput::new(&mut self.input)
+                                    .with_hint_text("Streams, #tags or people")
+                                    .with_frame(true),
+                            );
+                            let enter_pressed =
+                                input.has_focus() && ui.input(|i| i.key_pressed(Key::Enter));
+                            if enter_pressed {
+                                // single line search, drop the new line
+                                self.input = self.input.trim_end_matches('\n').to_string();
+                            }
+                            if Button::new().text(ui, "Search").clicked() || enter_pressed {
+                                self.submit(services);
+                            }
+                        });
+                    });
+                if self.query.is_empty() {
+                    return ui.response();
+                }
+
+                let relay_notes = self.relay_notes(services);
+                let profiles: Vec<[u8; 32]> = relay_notes
+                    .iter()
+                    .filter(|n| n.kind() == 0)
+                    .map(|n| *n.pubkey())
+                    .chain(self.profiles.iter().copied())
+                    .unique()
+                    .take(MAX_PROFILES)
+                    .collect();
+                if !profiles.is_empty() {
+                    Frame::none()
+                        .inner_margin(Margin::same(12.))
+                        .show(ui, |ui| {
+                            ui.label(RichText::new("People").size(24.));
+                            for pk in profiles.iter() {
+                                let rsp = Profile::new(pk)
+                                    .render(ui, services)
+                                    .interact(Sense::click())
+                                    .on_hover_cursor(CursorIcon::PointingHand);
+                                if rsp.clicked() {
+                                    services.navigate(RouteType::ProfilePage {
+                                        link: NostrLink::profile(pk),
+                                    });
+                                }
+                            }
+                        });
+                }
+
+                let events: Vec<Note> = self
+                    .streams
+                    .iter()
+                    .filter_map(|e| services.ctx.ndb.get_note_by_key(services.tx, e.key).ok())
+                    .chain(relay_notes.into_iter().filter(|n| n.kind() != 0))
+                    .unique_by(|n| *n.id())
+                    .collect();
+                if !events.is_empty() {
+                    StreamList::new(
+                        Id::new("search-streams"),
+                        NotesView::from_vec(events.iter().collect()),
+                        Some(RichText::new("Streams").size(24.)),
+                    )
+                    .render(ui, services);
+                }
+                if profiles.is_empty() && events.is_empty() {
+                    Frame::none()
+                        .inner_margin(Margin::same(12.))
+                        .show(ui, |ui| {
+                            ui.label(RichText::new("No results").color(NEUTRAL_500));
+                        });
+                }
+                ui.response()
+            })
+            .inner
+    }
+
+    fn update(&mut self, services: &mut RouteServices<'_, '_>) -> anyhow::Result<()> {
+        if self.query.is_empty() {
+            return Ok(());
+        }
+        if self.relay_sub.is_none() {
+            let id = format!("search-{}", Id::new(&self.query).value());
+            services
+                .relay_results
+                .subscribe(services.ctx.pool, id.clone(), self.relay_filters());
+            self.relay_sub = Some(id);
+        }
+        if !self.searched {
+            self.searched = true;
+            self.search_local(services.ctx.ndb, services.tx)?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,139 @@
+use crate::route::RouteServices;
+use crate::settings::{BackgroundPlayback, ContentWarnings};
+use crate::theme::{FONT_SIZE, NEUTRAL_500};
+use crate::widgets::{Button, NativeTextInput, NostrWidget, Profile};
+use egui::{Frame, Margin, Response, RichText, ScrollArea, Slider, Ui};
+use nostr::prelude::hex;
+
+/// Edit the local app settings
+pub struct SettingsPage;
+
+impl SettingsPage {
+    pub fn new() -> Self {
+        Self
+    }
+
+    fn section(ui: &mut Ui, title: &str, hint: &str) {
+        ui.add_space(8.);
+        ui.label(RichText::new(title).size(18.));
+        ui.label(RichText::new(hint).size(FONT_SIZE).color(NEUTRAL_500));
+    }
+}
+
+impl NostrWidget for SettingsPage {
+    fn render(&mut self, ui: &mut Ui, services: &mut RouteServices<'_, '_>) -> Response {
+        let mut changed = false;
+        ScrollArea::vertical().show(ui, |ui| {
+            Frame::none()
+                .inner_margin(Margin::same(12.))
+                .show(ui, |ui| {
+                    ui.spacing_mut().item_spacing.y = 8.;
+                    ui.label(RichText::new("Settings").size(32.));
+
+                    Self::section(
+                        ui,
+                        "Stale live streams",
+                        "Live streams without updates for this long are shown as ended",
+                    );
+                    changed |= ui
+                        .add(
+                            Slider::new(&mut services.settings.stale_live_minutes, 10..=24 * 60)
+                                .suffix(" min")
+                                .logarithmic(true),
+                        )
+                        .changed();
+
+                    Self::section(
+                        ui,
+                        "Background playback",
+                        "How streams keep playing after leaving the stream page",
+                    );
+                    ui.horizontal(|ui| {
+                        changed |= ui
+                            .radio_value(
+                                &mut services.settings.background_playback,
+                                BackgroundPlayback::MiniPlayer,
+                                "Mini player",
+                            )
+                            .changed();
+                        changed |= ui
+                            .radio_value(
+                                &mut services.settings.background_playback,
+                                BackgroundPlayback::AudioOnly,
+                                "Audio only",
+                            )
+                            .changed();
+                    });
+                    changed |= ui
+                        .checkbox(
+                            &mut services.settings.audio_only,
+                            "Play streams without video",
+                        )
+                        .on_hover_text(
+                            "Streams without an audio only version still download the video",
+                        )
+                        .changed();
+
+                    Self::section(
+                        ui,
+                        "Content warnings",
+                        "Streams and chat messages marked as sensitive",
+                    );
+                    ui.horizontal(|ui| {
+                        for (value, label) in [
+                            (ContentWarnings::Ask, "Ask"),
+                            (ContentWarnings::AlwaysShow, "Always show"),
+                            (ContentWarnings::AlwaysHide, "Always hide"),
+                        ] {
+                            changed |= ui
+                                .radio_value(&mut services.settings.content_warnings, value, label)
+                                .changed();
+                        }
+                    });
+
+                    Self::section(ui, "Blossom server", "Clips are uploaded to this server");
+                    changed |= ui
+                        .add(
+                            NativeTextInput::new(&mut services.settings.blossom_server)
+                                .with_frame(true),
+                        )
+                        .changed();
+
+                    Self::section(
+                        ui,
+                        "Live notifications",
+                        "Get notified when someone you follow goes live",
+                    );
+                    changed |= ui
+                        .checkbox(&mut services.settings.live_notifications, "Enabled")
+                        .changed();
+                    let muted: Vec<[u8; 32]> = services
+                        .settings
+                        .muted_streamers
+                        .iter()
+                        .filter_map(|pk| hex::decode(pk).ok()?.try_into().ok())
+                        .collect();
+                    if !muted.is_empty() {
+                        ui.label(RichText::new("Muted streamers").color(NEUTRAL_500));
+                    }
+                    for pk in muted.iter() {
+                        ui.horizontal(|ui| {
+                            Profile::new(pk).size(24.).render(ui, services);
+                            if Button::new().text(ui, "Unmute").clicked() {
+                                services.settings.muted_streamers.remove(&hex::encode(pk));
+                                changed = true;
+                            }
+                        });
+                    }
+                });
+        });
+        if changed {
+            services.settings.save_later();
+        }
+        ui.response()
+    }
+
+    fn update(&mut self, _services: &mut RouteServices<'_, '_>) -> anyhow::Result<()> {
+        Ok(())
+    }
+}
@@ -1,23 +1,59 @@
 use crate::link::NostrLink;
-use crate::route::RouteServices;
-use crate::theme::{MARGIN_DEFAULT, NEUTRAL_800, ROUNDING_DEFAULT};
+use crate::n94::N94Stream;
+use crate::playback;
+use crate::route::{RouteServices, RouteType};
+use crate::services::download::DownloadState;
+use crate::settings::{BackgroundPlayback, WindowRect};
+use crate::theme::{MARGIN_DEFAULT, NEUTRAL_500, NEUTRAL_800, ROUNDING_DEFAULT};
 use crate::widgets::{
-    sub_or_poll, Chat, NostrWidget, PlaceholderRect, StreamPlayer, StreamTitle, WriteChat,
+    sub_or_poll, Button, Chat, Clips, NostrWidget, PlaceholderRect, StreamPlayer, StreamTitle,
+    WriteChat,
 };
-use egui::{vec2, Align, Frame, Layout, Response, ScrollArea, Stroke, Ui, Vec2, Widget};
+use egui::{
+    pos2, vec2, Align, Color32, Context, Frame, Layout, OpenUrl, Response, RichText, ScrollArea,
+    Stroke, Ui, UiBuilder, Vec2, ViewportBuilder, ViewportId, Widget,
+};
+use log::warn;
 use nostrdb::{Filter, Note};
 
 use crate::note_ref::NoteRef;
-use crate::stream_info::StreamInfo;
+use crate::note_util::NoteUtil;
+use crate::stream_info::{StreamInfo, StreamStatus};
 use crate::sub::SubRef;
 use std::borrow::Borrow;
 use std::collections::HashSet;
+use std::time::{Duration, Instant};
+
+/// Wait for the pop out window to stop moving before saving its position
+const POPOUT_SAVE_DELAY: Duration = Duration::from_secs(1);
+
+/// Chat window state while it's popped out
+struct ChatPopout {
+    /// Saved window rect, only applied when opening
+    initial: Option<WindowRect>,
+    /// Current window rect and when it last changed
+    rect: Option<(WindowRect, Instant)>,
+}
 
 pub struct StreamPage {
     link: NostrLink,
-    player: Option<StreamPlayer>,
+    /// Playing back a recording, chat is replayed in sync with the player
+    replay: bool,
     chat: Option<Chat>,
+    clips: Clips,
+    /// Segment playlist of a n94 stream
+    n94: Option<N94Stream>,
+    /// Stream url which can't be played in the app
+    external: Option<String>,
+    /// Chat is shown in a separate window
+    chat_popout: Option<ChatPopout>,
     new_msg: WriteChat,
+    /// Content warning of the stream while playback is held back
+    content_warning: Option<String>,
+    /// Content warnings are set to always hide, the stream can't be shown
+    content_hidden: bool,
+    /// Play the stream despite its content warning
+    show_anyway: bool,
 
     events: HashSet<NoteRef>,
     sub: Option<SubRef>,
@@ -27,9 +63,16 @@ impl StreamPage {
     pub fn new_from_link(link: NostrLink) -> Self {
         Self {
             new_msg: WriteChat::new(link.clone()),
+            clips: Clips::new(link.clone()),
             link,
             chat: None,
-            player: None,
+            n94: None,
+            external: None,
+            chat_popout: None,
+            content_warning: None,
+            content_hidden: false,
+            show_anyway: false,
+            replay: false,
             events: HashSet::new(),
             sub: None,
         }
@@ -58,14 +101,23 @@ impl StreamPage {
                 let video_h =
                     ((ui.available_width() / 16.0) * 9.0).min(ui.available_height() * 0.33);
                 ui.allocate_ui(vec2(ui.available_width(), video_h), |ui| {
-                    if let Some(player) = &mut self.player {
+                    if let Some(player) = services.players.get(&self.link, self.replay) {
                         player.ui(ui)
                     } else {
-                        ui.add(PlaceholderRect)
+                        self.render_placeholder(ui, ui.available_size())
                     }
                 });
                 StreamTitle::new(event).render(ui, services);
 
+                if self.chat_popout.is_some() {
+                    ui.vertical_centered(|ui| {
+                        ui.label("Chat is open in another window");
+                        if Button::new().text(ui, "Show chat here").clicked() {
+                            self.close_chat_popout(services);
+                        }
+                    });
+                    return;
+                }
                 if let Some(c) = self.chat.as_mut() {
                     ui.allocate_ui(
                         vec2(ui.available_width(), ui.available_height() - chat_h),
@@ -91,7 +143,8 @@ impl StreamPage {
         services: &mut RouteServices<'_, '_>,
     ) -> Response {
         let max_h = ui.available_height();
-        let chat_w = 450.0;
+        let theatre = services.settings.theatre;
+        let chat_w = if theatre { 0.0 } else { 450.0 };
         let video_width = ui.available_width() - chat_w;
         let video_height = max_h.min((video_width / 16.0) * 9.0);
 
@@ -101,17 +154,46 @@ impl StreamPage {
                 Layout::top_down_justified(Align::Min),
                 |ui| {
                     ScrollArea::vertical().show(ui, |ui| {
-                        if let Some(player) = &mut self.player {
+                        if let Some(player) = services.players.get(&self.link, self.replay) {
                             ui.add_sized(vec2(video_width, video_height), player);
                         } else {
-                            ui.add_sized(vec2(video_width, video_height), PlaceholderRect);
+                            self.render_placeholder(ui, vec2(video_width, video_height));
                         }
 
                         ui.add_space(10.);
+                        ui.allocate_ui_with_layout(
+                            vec2(video_width, 32.),
+                            Layout::right_to_left(Align::Center),
+                            |ui| {
+                                let label = if theatre {
+                                    "Default view"
+                                } else {
+                                    "Theatre mode"
+                                };
+                                if Button::new().text(ui, label).clicked() {
+                                    services.settings.theatre = !theatre;
+                                    services.settings.save();
+                                }
+                                self.render_download(ui, event, services);
+                                if Button::new().text(ui, "Clip").clicked() {
+                                    self.clips.toggle_tool();
+                                }
+                                if Button::new().text(ui, "Mini player").clicked() {
+                                    services.settings.background_playback =
+                                        BackgroundPlayback::MiniPlayer;
+                                    services.settings.save();
+                                    services.navigate(RouteType::HomePage);
+                                }
+                            },
+                        );
                         StreamTitle::new(event).render(ui, services);
+                        self.clips.render(ui, services);
                     });
                 },
             );
+            if theatre {
+                return;
+            }
             ui.allocate_ui_with_layout(
                 vec2(chat_w, max_h),
                 Layout::top_down_justified(Align::Min),
@@ -121,21 +203,32 @@ impl StreamPage {
                         .outer_margin(MARGIN_DEFAULT)
                         .rounding(ROUNDING_DEFAULT)
                         .show(ui, |ui| {
-                            let chat_h = 60.0;
-                            if let Some(c) = self.chat.as_mut() {
-                                ui.allocate_ui(
-                                    vec2(ui.available_width(), ui.available_height() - chat_h),
-                                    |ui| {
-                                        c.render(ui, services);
-                                    },
-                                );
+                            if self.chat_popout.is_some() {
+                                ui.vertical_centered(|ui| {
+                                    ui.add_space(20.);
+                                    ui.label("Chat is open in another window");
+                                    if Button::new().text(ui, "Show chat here").clicked() {
+                                        self.close_chat_popout(services);
+                                    }
+                                });
+                                if ui.available_height().is_finite() {
+                                    ui.add_space(ui.available_height());
+                                }
                             } else {
-                                ui.label("Loading..");
-                            }
-                            if ui.available_height().is_finite() {
-                                ui.add_space(ui.available_height() - chat_h);
+                                // mobile and single window backends can't open native windows
+                                if cfg!(not(target_os = "android")) && !ui.ctx().embed_viewports() {
+                                    ui.allocate_ui_with_layout(
+                                        vec2(ui.available_width(), 32.),
+                                        Layout::right_to_left(Align::Center),
+                                        |ui| {
+                                            if Button::new().text(ui, "Pop out").clicked() {
+                                                self.open_chat_popout(services);
+                                            }
+                                        },
+                                    );
+                                }
+                                self.render_chat(ui, services);
                             }
-                            self.new_msg.render(ui, services);
                         });
                 },
             );
@@ -143,6 +236,193 @@ impl StreamPage {
 
         ui.response()
     }
+
+    /// Save the recording of an ended stream to the library
+    fn render_download(&self, ui: &mut Ui, event: &Note<'_>, services: &mut RouteServices<'_, '_>) {
+        let url = match event.recording() {
+            Some(r) if services.stream_status(event) == StreamStatus::Ended => r,
+            _ => return,
+        };
+        match services.downloads.state(url) {
+            Some(DownloadState::Downloading(p)) => {
+                ui.label(format!("Downloading {:.0}%", p * 100.0));
+            }
+            Some(DownloadState::Remuxing) => {
+                ui.label("Saving..");
+            }
+            Some(DownloadState::Done) => {
+                if Button::new().text(ui, "Downloaded").clicked() {
+                    services.navigate(RouteType::LibraryPage);
+                }
+            }
+            state => {
+                let rsp = Button::new().text(ui, "Download");
+                let rsp = if let Some(DownloadState::Failed(e)) = state {
+                    rsp.on_hover_text(format!("Download failed: {}", e))
+                } else {
+                    rsp
+                };
+                if rsp.clicked() {
+                    services
+                        .downloads
+                        .start(ui.ctx(), url, event.title().unwrap_or("Untitled"));
+                }
+            }
+        }
+    }
+
+    /// Only the player, filling the window
+    fn render_fullscreen(&mut self, ui: &mut Ui, services: &mut RouteServices<'_, '_>) -> Response {
+        let size = ui.available_size();
+        if let Some(player) = services.players.get(&self.link, self.replay) {
+            ui.add_sized(size, player)
+        } else {
+            self.render_placeholder(ui, size)
+        }
+    }
+
+    /// Empty player, with a link to open the stream in another app when it can't be played here
+    /// or the content warning prompt
+    fn render_placeholder(&mut self, ui: &mut Ui, size: Vec2) -> Response {
+        let rsp = ui.add_sized(size, PlaceholderRect);
+        let mut overlay = ui.new_child(
+            UiBuilder::new()
+                .max_rect(rsp.rect)
+                .layout(Layout::top_down(Align::Center)),
+        );
+        if let Some(reason) = &self.content_warning {
+            overlay.add_space(rsp.rect.height() / 2.0 - 50.0);
+            overlay.label(RichText::new("⚠ Content warning").size(20.));
+            if !reason.is_empty() {
+                overlay.label(reason);
+            }
+            if self.content_hidden {
+                overlay.label(
+                    RichText::new("Hidden because of your content warning settings")
+                        .color(NEUTRAL_500),
+                );
+            } else if Button::new().text(&mut overlay, "Show anyway").clicked() {
+                self.show_anyway = true;
+            }
+        } else if let Some(url) = &self.external {
+            overlay.add_space(rsp.rect.height() / 2.0 - 40.0);
+            overlay.label("This stream can't be played in the app");
+            if Button::new()
+                .text(&mut overlay, "Open externally")
+                .clicked()
+            {
+                ui.ctx().open_url(OpenUrl::new_tab(url));
+            }
+        }
+        rsp
+    }
+
+    /// Chat messages with the message composer below
+    fn render_chat(&mut self, ui: &mut Ui, services: &mut RouteServices<'_, '_>) {
+        let chat_h = 60.0;
+        if let Some(c) = self.chat.as_mut() {
+            ui.allocate_ui(
+                vec2(ui.available_width(), ui.available_height() - chat_h),
+                |ui| {
+                    c.render(ui, services);
+                },
+            );
+        } else {
+            ui.label("Loading..");
+        }
+        if ui.available_height().is_finite() {
+            ui.add_space(ui.available_height() - chat_h);
+        }
+        self.new_msg.render(ui, services);
+    }
+
+    /// Show the chat in a separate native window
+    fn render_chat_popout(
+        &mut self,
+        event: &Note<'_>,
+        ctx: &Context,
+        services: &mut RouteServices<'_, '_>,
+    ) {
+        let initial = match &self.chat_popout {
+            Some(p) => p.initial.clone(),
+            None => return,
+        };
+        // the builder must stay the same, changes are applied to the window every frame
+        let mut builder = ViewportBuilder::default()
+            .with_title(format!("Chat - {}", event.title().unwrap_or("Untitled")))
+            .with_inner_size(vec2(400., 700.));
+        if let Some(r) = &initial {
+            builder = builder
+                .with_position(pos2(r.x, r.y))
+                .with_inner_size(vec2(r.w, r.h));
+        }
+
+        let mut close = false;
+        let mut rect = None;
+        ctx.show_viewport_immediate(
+            ViewportId::from_hash_of("chat-popout"),
+            builder,
+            |ctx, _class| {
+                egui::CentralPanel::default()
+                    .frame(Frame::none().fill(Color32::BLACK))
+                    .show(ctx, |ui| {
+                        ui.vertical(|ui| self.render_chat(ui, services));
+                    });
+
+                let (close_requested, outer, inner) = ctx.input(|i| {
+                    let vp = i.viewport();
+                    (vp.close_requested(), vp.outer_rect, vp.inner_rect)
+                });
+                if let (Some(outer), Some(inner)) = (outer, inner) {
+                    rect = Some(WindowRect {
+                        x: outer.min.x,
+                        y: outer.min.y,
+                        w: inner.width(),
+                        h: inner.height(),
+                    });
+                }
+                close = close_requested;
+            },
+        );
+
+        if let (Some(rect), Some(p)) = (rect, self.chat_popout.as_mut()) {
+            if p.rect.as_ref().map(|(r, _)| r) != Some(&rect) {
+                p.rect = Some((rect, Instant::now()));
+                ctx.request_repaint_after(POPOUT_SAVE_DELAY);
+            }
+        }
+        if close {
+            self.close_chat_popout(services);
+        } else {
+            self.save_chat_window(services, false);
+        }
+    }
+
+    fn open_chat_popout(&mut self, services: &RouteServices<'_, '_>) {
+        self.chat_popout = Some(ChatPopout {
+            initial: services.settings.chat_window.clone(),
+            rect: None,
+        });
+    }
+
+    fn close_chat_popout(&mut self, services: &mut RouteServices<'_, '_>) {
+        self.save_chat_window(services, true);
+        self.chat_popout = None;
+    }
+
+    /// Remember the pop out window position, right away or once it stopped moving
+    fn save_chat_window(&self, services: &mut RouteServices<'_, '_>, now: bool) {
+        let (rect, changed) = match self.chat_popout.as_ref().and_then(|p| p.rect.as_ref()) {
+            Some(r) => r,
+            None => return,
+        };
+        if (now || changed.elapsed() >= POPOUT_SAVE_DELAY)
+            && services.settings.chat_window.as_ref() != Some(rect)
+        {
+            services.settings.chat_window = Some(rect.clone());
+            services.settings.save();
+        }
+    }
 }
 
 impl NostrWidget for StreamPage {
@@ -154,16 +434,53 @@ impl NostrWidget for StreamPage {
             .collect();
 
         if let Some(event) = events.first() {
-            if self.player.is_none() {
-                match event.kind() {
-                    30_311 => {
-                        if let Some(u) = event.streaming().or(event.recording()) {
-                            let p = StreamPlayer::new(ui.ctx(), &u.to_string());
-                            self.player = Some(p);
-                        }
-                    }
-                    _ => {}
+            let status = services.stream_status(event);
+            // switches to the recording when the stream ended while watching
+            self.replay = status == StreamStatus::Ended && event.recording().is_some();
+            if event.kind() == 30_313 && self.n94.is_none() {
+                self.n94 = Some(N94Stream::new(self.link.clone()));
+            }
+            let content_warning = event.content_warning();
+            self.content_hidden = services.settings.hides(content_warning);
+            let held = !self.show_anyway
+                && (self.content_hidden || services.settings.asks(content_warning));
+            self.content_warning = content_warning.filter(|_| held).map(|c| c.to_string());
+            if !held && services.players.get(&self.link, self.replay).is_none() {
+                let url = if let Some(n94) = self.n94.as_mut() {
+                    n94.write_playlist(status == StreamStatus::Ended)
+                        .unwrap_or_else(|e| {
+                            warn!("Failed to write n94 playlist: {}", e);
+                            None
+                        })
+                } else if self.replay {
+                    event.recording().map(|r| r.to_string())
+                } else if event.kind() == 30_311 {
+                    event
+                        .streaming()
+                        .or(event.recording())
+                        .map(|r| r.to_string())
+                } else {
+                    None
                 };
+                self.external = None;
+                match url {
+                    Some(u) if self.n94.is_some() || playback::can_play(&u) => {
+                        let p = StreamPlayer::new(ui.ctx(), &u);
+                        services.players.play(
+                            self.link.clone(),
+                            event.title().unwrap_or("Untitled"),
+                            self.replay,
+                            p,
+                        );
+                    }
+                    Some(u) => self.external = Some(u),
+                    None => {}
+                }
+            }
+            if let Some(p) = services.players.get(&self.link, self.replay) {
+                p.set_auto_reconnect(status == StreamStatus::Live);
+                p.set_volume(services.settings.volume, services.settings.muted);
+                p.set_audio_only(services.settings.audio_only);
             }
 
             if self.chat.is_none() {
@@ -172,11 +489,52 @@ impl NostrWidget for StreamPage {
                 self.chat = Some(chat);
             }
 
-            if ui.available_width() < 720.0 {
+            if let Some(c) = self.chat.as_mut() {
+                self.new_msg
+                    .set_chatters(c.recent_chatters(services.ctx.ndb, services.tx, 20));
+            }
+            self.new_msg.set_custom_emoji(event.emojis());
+            self.clips.set_replay(self.replay);
+
+            if self.replay {
+                if let (Some(player), Some(chat)) = (
+                    services.players.get(&self.link, self.replay),
+                    self.chat.as_mut(),
+                ) {
+                    // created_at of an ended stream is usually its last update, not the start
+                    let start = event.starts_tag().or(chat.first_event_at());
+                    chat.set_replay_position(start.map(|s| s + player.elapsed() as u64));
+                }
+            }
+
+            // the popped out chat stays open in every layout
+            if self.chat_popout.is_some() {
+                self.render_chat_popout(event, &ui.ctx().clone(), services);
+            }
+            let fullscreen = ui.ctx().input(|i| i.viewport().fullscreen.unwrap_or(false));
+            let rsp = if fullscreen {
+                self.render_fullscreen(ui, services)
+            } else if ui.available_width() < 720.0 {
                 self.render_mobile(event, ui, services)
             } else {
                 self.render_desktop(event, ui, services)
+            };
+
+            // remember volume and audio only changes from the player controls
+            if let Some(p) = services.players.get(&self.link, self.replay) {
+                let (volume, muted) = p.volume();
+                let audio_only = p.audio_only();
+                if services.settings.volume != volume
+                    || services.settings.muted != muted
+                    || services.settings.audio_only != audio_only
+                {
+                    services.settings.volume = volume;
+                    services.settings.muted = muted;
+                    services.settings.audio_only = audio_only;
+                    services.settings.save_later();
+                }
             }
+            rsp
         } else {
             ui.label("Loading..")
         }
@@ -195,6 +553,19 @@ impl NostrWidget for StreamPage {
         if let Some(c) = self.chat.as_mut() {
             c.update(services)?;
         }
+        self.clips.update(services)?;
+        if let Some(n94) = self.n94.as_mut() {
+            n94.update(services)?;
+            // new segments are picked up by the player reloading the playlist
+            if let Some(e) = self
+                .events
+                .iter()
+                .next()
+                .and_then(|e| services.ctx.ndb.get_note_by_key(services.tx, e.key).ok())
+            {
+                n94.write_playlist(services.stream_status(&e) == StreamStatus::Ended)?;
+            }
+        }
         Ok(())
     }
 }
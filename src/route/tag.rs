@@ -0,0 +1,132 @@
+use crate::note_ref::NoteRef;
+use crate::note_view::NotesView;
+use crate::route::RouteServices;
+use crate::stream_info::{StreamInfo, StreamStatus};
+use crate::sub::SubRef;
+use crate::theme::NEUTRAL_500;
+use crate::widgets::{sub_or_poll, NostrWidget, StreamList};
+use egui::{Frame, Id, Margin, Response, RichText, ScrollArea, Ui};
+use itertools::Itertools;
+use nostrdb::{Filter, Ndb, Note, Transaction};
+use std::collections::HashSet;
+
+/// Streams with a hashtag, matched case-insensitively
+pub struct TagPage {
+    tag: String,
+    /// Spellings of the tag to request, relays match `t` tags exactly
+    variants: Vec<String>,
+    events: HashSet<NoteRef>,
+    sub: Option<SubRef>,
+}
+
+impl TagPage {
+    pub fn new(tag: String) -> Self {
+        Self {
+            tag,
+            variants: vec![],
+            events: HashSet::new(),
+            sub: None,
+        }
+    }
+
+    fn get_filters(&self) -> Vec<Filter> {
+        vec![Filter::new()
+            .kinds([30_311, 30_313])
+            .tags(self.variants.clone(), 't')
+            .limit(100)
+            .build()]
+    }
+
+    /// Common case variants and the spellings used by streams which are already known
+    fn find_variants(&self, ndb: &Ndb, tx: &Transaction) -> anyhow::Result<Vec<String>> {
+        let lower = self.tag.to_lowercase();
+        let mut title = lower.clone();
+        if let Some(c) = title.get_mut(0..1) {
+            c.make_ascii_uppercase();
+        }
+        let mut variants = vec![self.tag.clone(), lower, title, self.tag.to_uppercase()];
+        let known = ndb.query(tx, &[Filter::new().kinds([30_311, 30_313]).build()], 1000)?;
+        for r in known {
+            variants.extend(
+                r.note
+                    .hashtags()
+                    .into_iter()
+                    .filter(|t| t.to_lowercase() == self.tag.to_lowercase())
+                    .map(|t| t.to_string()),
+            );
+        }
+        Ok(variants.into_iter().unique().collect())
+    }
+
+    fn matches(&self, note: &Note) -> bool {
+        let tag = self.tag.to_lowercase();
+        note.hashtags().iter().any(|t| t.to_lowercase() == tag)
+    }
+}
+
+impl NostrWidget for TagPage {
+    fn render(&mut self, ui: &mut Ui, services: &mut RouteServices<'_, '_>) -> Response {
+        ScrollArea::vertical()
+            .show(ui, |ui| {
+                Frame::none()
+                    .inner_margin(Margin::same(12.))
+                    .show(ui, |ui| {
+                        ui.label(RichText::new(format!("#{}", self.tag)).size(32.));
+                    });
+
+                let events: Vec<Note> = self
+                    .events
+                    .iter()
+                    .filter_map(|n| services.ctx.ndb.get_note_by_key(services.tx, n.key).ok())
+                    .filter(|n| self.matches(n))
+                    .collect();
+                if events.is_empty() {
+                    Frame::none()
+                        .inner_margin(Margin::same(12.))
+                        .show(ui, |ui| {
+                            ui.label(RichText::new("No streams found").color(NEUTRAL_500));
+                        });
+                }
+
+                for (status, heading) in [
+                    (StreamStatus::Live, "Live"),
+                    (StreamStatus::Planned, "Planned"),
+                    (StreamStatus::Ended, "Ended"),
+                ] {
+                    let list = NotesView::from_vec(
+                        events
+                            .iter()
+                            .filter(|e| {
+                                services.stream_status(e) == status
+                                    && (status != StreamStatus::Ended || e.recording().is_some())
+                            })
+                            .collect(),
+                    );
+                    if list.len() > 0 {
+                        StreamList::new(
+                            Id::new(("tag-streams", heading)),
+                            list,
+                            Some(RichText::new(heading).size(24.0)),
+                        )
+                        .render(ui, services);
+                    }
+                }
+                ui.response()
+            })
+            .inner
+    }
+
+    fn update(&mut self, services: &mut RouteServices<'_, '_>) -> anyhow::Result<()> {
+        if self.variants.is_empty() {
+            self.variants = self.find_variants(services.ctx.ndb, services.tx)?;
+        }
+        sub_or_poll(
+            services.ctx.ndb,
+            services.tx,
+            services.ctx.pool,
+            &mut self.events,
+            &mut self.sub,
+            self.get_filters(),
+        )
+    }
+}
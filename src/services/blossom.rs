@@ -0,0 +1,58 @@
+use anyhow::{anyhow, bail, Result};
+use base64::Engine;
+use nostr::hashes::sha256::Hash as Sha256Hash;
+use nostr::hashes::Hash;
+use nostr::{serde_json, EventBuilder, JsonUtil, Keys, Kind, Tag, Timestamp};
+use serde::Deserialize;
+
+/// How long the upload authorization is valid for
+const AUTH_EXPIRE_SECS: u64 = 300;
+
+/// Blob returned by a Blossom server after uploading
+#[derive(Debug, Clone, Deserialize)]
+pub struct BlobDescriptor {
+    pub url: String,
+    pub sha256: String,
+    pub size: u64,
+    #[serde(rename = "type")]
+    pub mime_type: Option<String>,
+}
+
+/// Upload a file to a Blossom server (BUD-02), blocking
+pub fn upload(server: &str, keys: &Keys, data: Vec<u8>, mime_type: &str) -> Result<BlobDescriptor> {
+    let hash = Sha256Hash::hash(&data).to_string();
+    let auth = EventBuilder::new(Kind::Custom(24_242), "Upload clip")
+        .tags([
+            Tag::parse(vec!["t".to_string(), "upload".to_string()])?,
+            Tag::parse(vec!["x".to_string(), hash.clone()])?,
+            Tag::expiration(Timestamp::now() + AUTH_EXPIRE_SECS),
+        ])
+        .sign_with_keys(keys)?;
+
+    let auth = format!(
+        "Nostr {}",
+        base64::engine::general_purpose::STANDARD.encode(auth.as_json())
+    );
+    let mut req = ehttp::Request::post(format!("{}/upload", server.trim_end_matches('/')), data);
+    req.method = "PUT".to_string();
+    req.headers = ehttp::Headers::new(&[
+        ("Accept", "application/json"),
+        ("Content-Type", mime_type),
+        ("Authorization", &auth),
+    ]);
+
+    let rsp = ehttp::fetch_blocking(&req).map_err(|e| anyhow!(e))?;
+    if !rsp.ok {
+        let reason = rsp
+            .headers
+            .get("x-reason")
+            .unwrap_or(&rsp.status_text)
+            .to_string();
+        bail!("Upload failed: {} {}", rsp.status, reason);
+    }
+    let blob: BlobDescriptor = serde_json::from_slice(&rsp.bytes)?;
+    if blob.sha256 != hash {
+        bail!("Upload failed: hash mismatch");
+    }
+    Ok(blob)
+}
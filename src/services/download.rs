@@ -0,0 +1,380 @@
+use crate::hls::{parse_master, resolve_url, write_media_playlist, HlsSegment};
+use anyhow::{anyhow, bail, Result};
+use egui::Context;
+use egui_video::ffmpeg_rs_raw::Transcoder;
+use log::{info, warn};
+use m3u8_rs::{KeyMethod, Playlist};
+use nostr::serde_json;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::fs::File;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+/// Size of the range requests when downloading a single file
+const CHUNK_SIZE: u64 = 4 * 1024 * 1024;
+
+/// How long a failed download is shown before it's removed
+const FAILED_TIMEOUT: Duration = Duration::from_secs(10);
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum DownloadState {
+    /// Fraction downloaded
+    Downloading(f32),
+    /// Converting the HLS segments into a single MP4 file
+    Remuxing,
+    Done,
+    Failed(String),
+}
+
+/// A recording saved in the local library
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LibraryEntry {
+    pub title: String,
+    /// File name in the downloads directory
+    pub file: String,
+    /// Source url of the recording
+    pub url: String,
+    /// Unix timestamp of the download
+    pub created: u64,
+    /// File size in bytes
+    #[serde(default)]
+    pub size: u64,
+}
+
+pub struct Download {
+    pub entry: LibraryEntry,
+    state: Arc<Mutex<DownloadState>>,
+    /// When the download was first seen failed
+    failed: Option<Instant>,
+}
+
+impl Download {
+    pub fn state(&self) -> DownloadState {
+        self.state.lock().unwrap().clone()
+    }
+}
+
+/// Downloads recordings into a local library which can be played offline
+pub struct Downloads {
+    dir: PathBuf,
+    library: Vec<LibraryEntry>,
+    active: Vec<Download>,
+}
+
+impl Downloads {
+    pub fn new(data_path: &Path) -> Self {
+        let dir = data_path.join("downloads");
+        let library = match std::fs::read(dir.join("library.json")) {
+            Ok(json) => serde_json::from_slice(&json).unwrap_or_else(|e| {
+                warn!("Failed to parse library: {}", e);
+                vec![]
+            }),
+            Err(_) => vec![],
+        };
+        Self {
+            dir,
+            library,
+            active: vec![],
+        }
+    }
+
+    /// Finished downloads, newest first
+    pub fn library(&self) -> &[LibraryEntry] {
+        &self.library
+    }
+
+    pub fn active(&self) -> &[Download] {
+        &self.active
+    }
+
+    /// Directory where downloads are saved
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// Local path of a library entry
+    pub fn path(&self, entry: &LibraryEntry) -> PathBuf {
+        self.dir.join(&entry.file)
+    }
+
+    /// State of the most recent download of this url
+    pub fn state(&self, url: &str) -> Option<DownloadState> {
+        if let Some(d) = self.active.iter().rev().find(|d| d.entry.url == url) {
+            Some(d.state())
+        } else if self.library.iter().any(|e| e.url == url) {
+            Some(DownloadState::Done)
+        } else {
+            None
+        }
+    }
+
+    /// Download a recording in the background
+    pub fn start(&mut self, ctx: &Context, url: &str, title: &str) {
+        let created = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_secs();
+        let ext = if url.contains(".m3u8") {
+            "mp4"
+        } else {
+            url.rsplit_once('.')
+                .map(|(_, e)| e)
+                .filter(|e| e.len() <= 4 && e.chars().all(|c| c.is_ascii_alphanumeric()))
+                .unwrap_or("mp4")
+        };
+        let name: String = title
+            .chars()
+            .map(|c| if c.is_alphanumeric() { c } else { '_' })
+            .take(64)
+            .collect();
+        let entry = LibraryEntry {
+            title: title.to_string(),
+            file: format!("{}-{}.{}", name, created, ext),
+            url: url.to_string(),
+            created,
+            size: 0,
+        };
+        let state = Arc::new(Mutex::new(DownloadState::Downloading(0.0)));
+
+        let out = self.dir.join(&entry.file);
+        let url = url.to_string();
+        let ctx = ctx.clone();
+        let worker_state = state.clone();
+        std::thread::spawn(move || {
+            let set = |s: DownloadState| {
+                *worker_state.lock().unwrap() = s;
+                ctx.request_repaint();
+            };
+            info!("Downloading {} to {}", url, out.display());
+            let result = if url.contains(".m3u8") {
+                download_hls(&url, &out, &set)
+            } else {
+                download_file(&url, &out, &set)
+            };
+            match result {
+                Ok(_) => set(DownloadState::Done),
+                Err(e) => {
+                    warn!("Download failed {}: {}", url, e);
+                    let _ = std::fs::remove_file(&out);
+                    let _ = std::fs::remove_dir_all(out.with_extension("parts"));
+                    set(DownloadState::Failed(e.to_string()));
+                }
+            }
+        });
+        self.active.push(Download {
+            entry,
+            state,
+            failed: None,
+        });
+    }
+
+    /// Move finished downloads into the library, failed downloads are removed
+    /// after [FAILED_TIMEOUT]
+    pub fn update(&mut self, ctx: &Context) {
+        for d in self.active.iter_mut() {
+            if d.failed.is_none() && matches!(d.state(), DownloadState::Failed(_)) {
+                d.failed = Some(Instant::now());
+                ctx.request_repaint_after(FAILED_TIMEOUT);
+            }
+        }
+        self.active
+            .retain(|d| d.failed.map_or(true, |t| t.elapsed() < FAILED_TIMEOUT));
+        let (done, active): (Vec<Download>, Vec<Download>) = self
+            .active
+            .drain(..)
+            .partition(|d| d.state() == DownloadState::Done);
+        self.active = active;
+        if !done.is_empty() {
+            for mut d in done {
+                d.entry.size = std::fs::metadata(self.path(&d.entry))
+                    .map(|m| m.len())
+                    .unwrap_or(0);
+                self.library.insert(0, d.entry);
+            }
+            self.save();
+        }
+    }
+
+    /// Delete a download from disk and the library
+    pub fn remove(&mut self, file: &str) {
+        if let Err(e) = std::fs::remove_file(self.dir.join(file)) {
+            warn!("Failed to delete {}: {}", file, e);
+        }
+        self.library.retain(|e| e.file != file);
+        self.active.retain(|d| d.entry.file != file);
+        self.save();
+    }
+
+    fn save(&self) {
+        let json = match serde_json::to_vec_pretty(&self.library) {
+            Ok(j) => j,
+            Err(e) => {
+                warn!("Failed to serialize library: {}", e);
+                return;
+            }
+        };
+        if let Err(e) = std::fs::create_dir_all(&self.dir)
+            .and_then(|_| std::fs::write(self.dir.join("library.json"), json))
+        {
+            warn!("Failed to save library: {}", e);
+        }
+    }
+}
+
+fn get(url: &str) -> Result<Vec<u8>> {
+    let rsp = ehttp::fetch_blocking(&ehttp::Request::get(url)).map_err(|e| anyhow!(e))?;
+    if !rsp.ok {
+        bail!("Failed to download {} ({})", url, rsp.status);
+    }
+    Ok(rsp.bytes)
+}
+
+/// Download the highest quality variant segment by segment, then remux it into an MP4 file
+fn download_hls(url: &str, out: &Path, set: &impl Fn(DownloadState)) -> Result<()> {
+    let data = get(url)?;
+    let variants = parse_master(url, &data)?;
+    let (media_url, data) = match variants.first() {
+        Some(v) => (v.url.clone(), get(&v.url)?),
+        None => (url.to_string(), data),
+    };
+    let playlist = match m3u8_rs::parse_playlist_res(&data) {
+        Ok(Playlist::MediaPlaylist(m)) => m,
+        _ => bail!("Invalid media playlist"),
+    };
+    if playlist
+        .segments
+        .iter()
+        .filter_map(|s| s.key.as_ref())
+        .any(|k| k.method != KeyMethod::None)
+    {
+        bail!("Encrypted recordings can't be downloaded");
+    }
+    let maps: HashSet<&str> = playlist
+        .segments
+        .iter()
+        .filter_map(|s| s.map.as_ref())
+        .map(|m| m.uri.as_str())
+        .collect();
+    if maps.len() > 1 {
+        bail!("Recordings with multiple init segments can't be downloaded");
+    }
+    let init = match maps.into_iter().next() {
+        Some(uri) => Some(resolve_url(&media_url, uri)?),
+        None => None,
+    };
+    let segments = playlist
+        .segments
+        .iter()
+        .enumerate()
+        .map(|(i, s)| {
+            Ok(HlsSegment {
+                sequence: i as u64,
+                url: resolve_url(&media_url, &s.uri)?,
+                duration: s.duration,
+            })
+        })
+        .collect::<Result<Vec<_>>>()?;
+    save_segments(&segments, init.as_deref(), out, set)
+}
+
+/// Download HLS segments and remux them into a single file, `init` is the
+/// `EXT-X-MAP` segment of fMP4 streams
+pub fn save_segments(
+    segments: &[HlsSegment],
+    init: Option<&str>,
+    out: &Path,
+    set: &impl Fn(DownloadState),
+) -> Result<()> {
+    if segments.is_empty() {
+        bail!("Nothing to save");
+    }
+    let tmp = out.with_extension("parts");
+    std::fs::create_dir_all(&tmp)?;
+
+    let local_init = match init {
+        Some(url) => {
+            std::fs::write(tmp.join("init.mp4"), get(url)?)?;
+            Some("init.mp4")
+        }
+        None => None,
+    };
+    let ext = if init.is_some() { "m4s" } else { "ts" };
+    let mut local = Vec::with_capacity(segments.len());
+    for (i, s) in segments.iter().enumerate() {
+        let name = format!("{}.{}", i, ext);
+        std::fs::write(tmp.join(&name), get(&s.url)?)?;
+        local.push(HlsSegment {
+            sequence: i as u64,
+            url: name,
+            duration: s.duration,
+        });
+        set(DownloadState::Downloading(
+            (i + 1) as f32 / segments.len() as f32,
+        ));
+    }
+    let playlist = tmp.join("index.m3u8");
+    std::fs::write(&playlist, write_media_playlist(&local, local_init, true))?;
+
+    set(DownloadState::Remuxing);
+    let result = remux(&playlist, out);
+    std::fs::remove_dir_all(&tmp)?;
+    result
+}
+
+/// Download a single file using range requests to report progress
+fn download_file(url: &str, out: &Path, set: &impl Fn(DownloadState)) -> Result<()> {
+    if let Some(dir) = out.parent() {
+        std::fs::create_dir_all(dir)?;
+    }
+    let mut file = File::create(out)?;
+    let mut offset = 0;
+    loop {
+        let mut req = ehttp::Request::get(url);
+        req.headers.insert(
+            "Range",
+            format!("bytes={}-{}", offset, offset + CHUNK_SIZE - 1),
+        );
+        let rsp = ehttp::fetch_blocking(&req).map_err(|e| anyhow!(e))?;
+        if !rsp.ok {
+            bail!("Failed to download {} ({})", url, rsp.status);
+        }
+        file.write_all(&rsp.bytes)?;
+        offset += rsp.bytes.len() as u64;
+
+        // servers without range support send the whole file
+        let total = rsp
+            .headers
+            .get("content-range")
+            .and_then(|r| r.rsplit('/').next())
+            .and_then(|t| t.parse::<u64>().ok());
+        match total {
+            Some(t) if offset < t && !rsp.bytes.is_empty() => {
+                set(DownloadState::Downloading(offset as f32 / t as f32))
+            }
+            _ => break,
+        }
+    }
+    Ok(())
+}
+
+/// Copy the audio and video streams into a new container
+fn remux(input: &Path, output: &Path) -> Result<()> {
+    unsafe {
+        let mut transcoder = Transcoder::new(
+            input.to_str().ok_or(anyhow!("Invalid path"))?,
+            output.to_str().ok_or(anyhow!("Invalid path"))?,
+        )?;
+        let info = transcoder.prepare()?;
+        if let Some(v) = info.best_video() {
+            transcoder.copy_stream(v)?;
+        }
+        if let Some(a) = info.best_audio() {
+            transcoder.copy_stream(a)?;
+        }
+        transcoder.run(None)?;
+    }
+    Ok(())
+}
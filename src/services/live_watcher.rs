@@ -0,0 +1,137 @@
+use crate::contacts::Contacts;
+use crate::link::NostrLink;
+use crate::note_ref::NoteRef;
+use crate::route::RouteServices;
+use crate::services::notifications::{Alert, Notifications};
+use crate::stream_info::{StreamInfo, StreamStatus};
+use crate::sub::SubRef;
+use crate::widgets::sub_or_poll;
+use anyhow::Result;
+use nostr::prelude::hex;
+use nostrdb::{Filter, Note};
+use std::collections::{HashMap, HashSet};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Notifies when a followed streamer goes live
+pub struct LiveWatcher {
+    contacts: Contacts,
+    follows: HashSet<[u8; 32]>,
+    events: HashSet<NoteRef>,
+    sub: Option<SubRef>,
+    /// Number of events which were checked for status changes
+    checked: usize,
+    /// Last seen status of each stream by `a` tag value, going live is a change to [StreamStatus::Live]
+    status: HashMap<String, StreamStatus>,
+    /// Streams first seen live which already started when the app was opened are not notified
+    started: u64,
+}
+
+impl LiveWatcher {
+    pub fn new() -> Self {
+        Self {
+            contacts: Contacts::new(),
+            follows: HashSet::new(),
+            events: HashSet::new(),
+            sub: None,
+            checked: 0,
+            status: HashMap::new(),
+            started: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .unwrap()
+                .as_secs(),
+        }
+    }
+
+    pub fn update(
+        &mut self,
+        services: &mut RouteServices<'_, '_>,
+        notifications: &mut Notifications,
+    ) -> Result<()> {
+        self.contacts.update(services)?;
+        let follows = self.contacts.follows(services.ctx.ndb, services.tx);
+        if follows != self.follows {
+            self.follows = follows;
+            self.events.clear();
+            self.sub = None;
+            self.checked = 0;
+        }
+        if self.follows.is_empty() || !services.settings.live_notifications {
+            return Ok(());
+        }
+        sub_or_poll(
+            services.ctx.ndb,
+            services.tx,
+            services.ctx.pool,
+            &mut self.events,
+            &mut self.sub,
+            vec![
+                Filter::new()
+                    .kinds([30_311])
+                    .authors(&self.follows)
+                    .limit(100)
+                    .build(),
+                // streams published by a service with the streamer as host
+                Filter::new()
+                    .kinds([30_311])
+                    .pubkeys(&self.follows)
+                    .limit(100)
+                    .build(),
+            ],
+        )?;
+        if self.events.len() == self.checked {
+            return Ok(());
+        }
+        self.checked = self.events.len();
+
+        // latest event of each stream
+        let mut latest: Vec<Note> = Vec::new();
+        for e in self.events.iter() {
+            let note = match services.ctx.ndb.get_note_by_key(services.tx, e.key) {
+                Ok(n) => n,
+                Err(_) => continue,
+            };
+            let stream = NostrLink::from_note(&note).to_tag_value();
+            match latest
+                .iter_mut()
+                .find(|n| NostrLink::from_note(n).to_tag_value() == stream)
+            {
+                Some(n) if n.created_at() < note.created_at() => *n = note,
+                Some(_) => {}
+                None => latest.push(note),
+            }
+        }
+
+        for note in latest {
+            let stream = NostrLink::from_note(&note).to_tag_value();
+            let status = services.stream_status(&note);
+            let prev = self.status.insert(stream, status.clone());
+            if status != StreamStatus::Live || prev == Some(StreamStatus::Live) {
+                continue;
+            }
+            // a planned stream which was seen before going live is always notified
+            if prev.is_none() && note.starts() < self.started {
+                continue;
+            }
+            let host = note.host();
+            if !self.follows.contains(host)
+                || services
+                    .settings
+                    .muted_streamers
+                    .contains(&hex::encode(host))
+            {
+                continue;
+            }
+            let name = services
+                .profile(host)
+                .and_then(|p| p.display_name().or(p.name()))
+                .map(|n| n.to_string())
+                .unwrap_or_else(|| "Someone you follow".to_string());
+            notifications.push(Alert {
+                title: format!("{} is live", name),
+                body: note.title().unwrap_or("Untitled").to_string(),
+                link: Some(NostrLink::from_note(&note)),
+            });
+        }
+        Ok(())
+    }
+}
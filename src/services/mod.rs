@@ -1 +1,6 @@
+pub mod blossom;
+pub mod download;
 pub mod ffmpeg_loader;
+pub mod live_watcher;
+pub mod notifications;
+pub mod reminders;
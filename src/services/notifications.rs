@@ -0,0 +1,61 @@
+use crate::link::NostrLink;
+use log::{info, warn};
+
+/// A notification which is also shown inside the app until dismissed
+#[derive(Clone)]
+pub struct Alert {
+    pub title: String,
+    pub body: String,
+    /// Stream to open from the alert
+    pub link: Option<NostrLink>,
+}
+
+/// System notifications with in-app alerts as fallback
+pub struct Notifications {
+    alerts: Vec<Alert>,
+}
+
+impl Notifications {
+    pub fn new() -> Self {
+        Self { alerts: vec![] }
+    }
+
+    /// Show a system notification and keep the alert in the app
+    pub fn push(&mut self, alert: Alert) {
+        info!("Notification: {} - {}", alert.title, alert.body);
+        notify(&alert.title, &alert.body);
+        self.alerts.push(alert);
+    }
+
+    /// Alerts which haven't been dismissed
+    pub fn alerts(&self) -> &[Alert] {
+        &self.alerts
+    }
+
+    pub fn dismiss(&mut self, idx: usize) {
+        if idx < self.alerts.len() {
+            self.alerts.remove(idx);
+        }
+    }
+}
+
+#[cfg(not(target_os = "android"))]
+fn notify(title: &str, body: &str) {
+    if let Err(e) = notify_rust::Notification::new()
+        .appname("zap.stream")
+        .summary(title)
+        .body(body)
+        .show()
+    {
+        warn!("Failed to show notification: {}", e);
+    }
+}
+
+/// The native activity has no notification api without JNI, only the in-app alert is shown
+#[cfg(target_os = "android")]
+fn notify(title: &str, _body: &str) {
+    info!(
+        "System notifications are not available, showing in-app alert: {}",
+        title
+    );
+}
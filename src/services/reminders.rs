@@ -0,0 +1,239 @@
+use crate::link::NostrLink;
+use crate::note_ref::NoteRef;
+use crate::services::notifications::{Alert, Notifications};
+use crate::stream_info::{StreamInfo, StreamStatus};
+use crate::sub::SubRef;
+use crate::widgets::sub_or_poll;
+use anyhow::{anyhow, Result};
+use chrono::{DateTime, Utc};
+use enostr::RelayPool;
+use log::warn;
+use nostr::serde_json;
+use nostrdb::{Filter, Ndb, Note, Transaction};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+/// How often reminders are checked
+const CHECK_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Reminders are removed this long after the stream started
+const EXPIRE_SECS: u64 = 24 * 60 * 60;
+
+/// Length of the calendar entry when the stream has no end time
+const DEFAULT_DURATION_SECS: u64 = 60 * 60;
+
+/// A planned stream the user wants to be notified about
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Reminder {
+    /// `a` tag value of the stream
+    pub stream: String,
+    pub title: String,
+    pub starts: u64,
+    #[serde(default)]
+    pub notified: bool,
+}
+
+impl Reminder {
+    pub fn link(&self) -> Option<NostrLink> {
+        NostrLink::from_tag_value(&self.stream).ok()
+    }
+}
+
+/// Reminders for planned streams, saved in the data directory
+pub struct Reminders {
+    path: PathBuf,
+    reminders: Vec<Reminder>,
+
+    /// Stream events of the reminders, to notice when they go live
+    events: HashSet<NoteRef>,
+    sub: Option<SubRef>,
+    next_check: Instant,
+}
+
+impl Reminders {
+    pub fn new(data_path: &Path) -> Self {
+        let path = data_path.join("reminders.json");
+        let reminders = match std::fs::read(&path) {
+            Ok(json) => serde_json::from_slice(&json).unwrap_or_else(|e| {
+                warn!("Failed to parse reminders: {}", e);
+                vec![]
+            }),
+            Err(_) => vec![],
+        };
+        Self {
+            path,
+            reminders,
+            events: HashSet::new(),
+            sub: None,
+            next_check: Instant::now(),
+        }
+    }
+
+    pub fn is_set(&self, link: &NostrLink) -> bool {
+        let stream = link.to_tag_value();
+        self.reminders.iter().any(|r| r.stream == stream)
+    }
+
+    /// Add or remove the reminder for a stream
+    pub fn toggle(&mut self, event: &Note<'_>) {
+        let stream = NostrLink::from_note(event).to_tag_value();
+        if self.reminders.iter().any(|r| r.stream == stream) {
+            self.reminders.retain(|r| r.stream != stream);
+        } else {
+            self.reminders.push(Reminder {
+                stream,
+                title: event.title().unwrap_or("Untitled").to_string(),
+                starts: event.starts(),
+                notified: false,
+            });
+        }
+        self.sub = None;
+        self.next_check = Instant::now();
+        self.save();
+    }
+
+    /// Fire reminders when the stream starts or goes live
+    pub fn update(
+        &mut self,
+        ndb: &Ndb,
+        tx: &Transaction,
+        pool: &mut RelayPool,
+        notifications: &mut Notifications,
+    ) -> Result<()> {
+        let filters: Vec<Filter> = self
+            .reminders
+            .iter()
+            .filter(|r| !r.notified)
+            .filter_map(|r| r.link())
+            .filter_map(|l| (&l).try_into().ok())
+            .collect();
+        if filters.is_empty() {
+            self.sub = None;
+        } else {
+            sub_or_poll(ndb, tx, pool, &mut self.events, &mut self.sub, filters)?;
+        }
+
+        if Instant::now() < self.next_check {
+            return Ok(());
+        }
+        self.next_check = Instant::now() + CHECK_INTERVAL;
+
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_secs();
+        let events: Vec<Note> = self
+            .events
+            .iter()
+            .filter_map(|e| ndb.get_note_by_key(tx, e.key).ok())
+            .collect();
+        let mut changed = false;
+        for r in self.reminders.iter_mut().filter(|r| !r.notified) {
+            let latest = events
+                .iter()
+                .filter(|e| NostrLink::from_note(e).to_tag_value() == r.stream)
+                .max_by_key(|e| e.created_at());
+            if let Some(e) = latest {
+                // stream was rescheduled
+                if e.status() == StreamStatus::Planned && e.starts() != r.starts {
+                    r.starts = e.starts();
+                    changed = true;
+                }
+            }
+            let live = latest.map_or(false, |e| e.status() == StreamStatus::Live);
+            if live || r.starts <= now {
+                notifications.push(Alert {
+                    title: r.title.clone(),
+                    body: if live { "Live now" } else { "Starting now" }.to_string(),
+                    link: r.link(),
+                });
+                r.notified = true;
+                changed = true;
+            }
+        }
+        let count = self.reminders.len();
+        self.reminders
+            .retain(|r| !r.notified || r.starts + EXPIRE_SECS > now);
+        if changed || count != self.reminders.len() {
+            self.save();
+        }
+        Ok(())
+    }
+
+    fn save(&self) {
+        let json = match serde_json::to_vec_pretty(&self.reminders) {
+            Ok(j) => j,
+            Err(e) => {
+                warn!("Failed to serialize reminders: {}", e);
+                return;
+            }
+        };
+        if let Err(e) = self
+            .path
+            .parent()
+            .map_or(Ok(()), std::fs::create_dir_all)
+            .and_then(|_| std::fs::write(&self.path, json))
+        {
+            warn!("Failed to save reminders: {}", e);
+        }
+    }
+}
+
+/// Escape text for an iCalendar property value
+fn ics_text(s: &str) -> String {
+    s.replace('\\', "\\\\")
+        .replace(';', "\\;")
+        .replace(',', "\\,")
+        .replace('\n', "\\n")
+}
+
+fn ics_time(t: u64) -> String {
+    DateTime::<Utc>::from_timestamp(t as i64, 0)
+        .unwrap_or_default()
+        .format("%Y%m%dT%H%M%SZ")
+        .to_string()
+}
+
+/// Calendar entry (RFC 5545) for a stream
+pub fn to_ics(event: &Note<'_>) -> String {
+    let starts = event.starts();
+    let ends = event.ends().unwrap_or(starts + DEFAULT_DURATION_SECS);
+    let mut out =
+        String::from("BEGIN:VCALENDAR\r\nVERSION:2.0\r\nPRODID:-//zap.stream//app//EN\r\n");
+    out.push_str("BEGIN:VEVENT\r\n");
+    out.push_str(&format!(
+        "UID:{}@zap.stream\r\n",
+        ics_text(&NostrLink::from_note(event).to_tag_value())
+    ));
+    out.push_str(&format!("DTSTAMP:{}\r\n", ics_time(event.created_at())));
+    out.push_str(&format!("DTSTART:{}\r\n", ics_time(starts)));
+    out.push_str(&format!("DTEND:{}\r\n", ics_time(ends)));
+    out.push_str(&format!(
+        "SUMMARY:{}\r\n",
+        ics_text(event.title().unwrap_or("Untitled"))
+    ));
+    if let Some(summary) = event.summary() {
+        out.push_str(&format!("DESCRIPTION:{}\r\n", ics_text(summary)));
+    }
+    out.push_str("END:VEVENT\r\nEND:VCALENDAR\r\n");
+    out
+}
+
+/// Save the calendar entry of a stream to the downloads folder
+pub fn export_ics(event: &Note<'_>) -> Result<PathBuf> {
+    let dir = directories::UserDirs::new()
+        .and_then(|d| d.download_dir().map(|p| p.to_path_buf()))
+        .ok_or(anyhow!("No downloads folder"))?;
+    let name: String = event
+        .title()
+        .unwrap_or("stream")
+        .chars()
+        .map(|c| if c.is_alphanumeric() { c } else { '_' })
+        .take(64)
+        .collect();
+    let path = dir.join(format!("{}.ics", name));
+    std::fs::write(&path, to_ics(event))?;
+    Ok(path)
+}
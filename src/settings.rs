@@ -0,0 +1,160 @@
+use egui::Context;
+use log::{info, warn};
+use nostr::serde_json;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+/// Time without changes before [Settings::save_later] writes the file
+const SAVE_DELAY: Duration = Duration::from_secs(1);
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct WindowRect {
+    pub x: f32,
+    pub y: f32,
+    pub w: f32,
+    pub h: f32,
+}
+
+/// How a stream keeps playing after leaving the stream page
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
+pub enum BackgroundPlayback {
+    #[default]
+    MiniPlayer,
+    AudioOnly,
+}
+
+/// Order of the streams on the home page
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
+pub enum StreamSort {
+    /// Newest first
+    #[default]
+    Starts,
+    Viewers,
+    /// Most zapped first
+    Zaps,
+}
+
+/// Handling of streams and messages with a NIP-36 content warning
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
+pub enum ContentWarnings {
+    /// Blur / collapse until the user chooses to show it
+    #[default]
+    Ask,
+    AlwaysShow,
+    AlwaysHide,
+}
+
+/// Local app settings, saved as json in the data directory
+#[derive(Serialize, Deserialize)]
+#[serde(default)]
+pub struct Settings {
+    /// Last position of the chat pop-out window
+    pub chat_window: Option<WindowRect>,
+    /// Player volume 0-1
+    pub volume: f32,
+    pub muted: bool,
+    /// Play streams without video
+    pub audio_only: bool,
+    /// Hide the chat next to the player on desktop
+    pub theatre: bool,
+    pub background_playback: BackgroundPlayback,
+    /// Blossom server for uploading clips
+    pub blossom_server: String,
+    pub stream_sort: StreamSort,
+    /// Treat live streams as ended when they haven't been updated for this long
+    pub stale_live_minutes: u64,
+    /// Notify when a followed streamer goes live
+    pub live_notifications: bool,
+    /// Hex pubkeys of followed streamers without live notifications
+    pub muted_streamers: HashSet<String>,
+    pub content_warnings: ContentWarnings,
+
+    #[serde(skip)]
+    path: PathBuf,
+    /// When an unsaved change was made by [Settings::save_later]
+    #[serde(skip)]
+    dirty: Option<Instant>,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            chat_window: None,
+            volume: 1.0,
+            muted: false,
+            audio_only: false,
+            theatre: false,
+            background_playback: BackgroundPlayback::default(),
+            blossom_server: "https://nostr.download".to_string(),
+            stream_sort: StreamSort::default(),
+            stale_live_minutes: 60,
+            live_notifications: true,
+            muted_streamers: HashSet::new(),
+            content_warnings: ContentWarnings::default(),
+            path: PathBuf::new(),
+            dirty: None,
+        }
+    }
+}
+
+impl Settings {
+    pub fn load(data_path: &Path) -> Self {
+        let path = data_path.join("settings.json");
+        let mut settings = match std::fs::read(&path) {
+            Ok(json) => serde_json::from_slice(&json).unwrap_or_else(|e| {
+                warn!("Failed to parse settings: {}", e);
+                Settings::default()
+            }),
+            Err(_) => Settings::default(),
+        };
+        settings.path = path;
+        settings
+    }
+
+    /// Content with this warning is never shown
+    pub fn hides(&self, content_warning: Option<&str>) -> bool {
+        content_warning.is_some() && self.content_warnings == ContentWarnings::AlwaysHide
+    }
+
+    /// Content with this warning is only shown after confirming
+    pub fn asks(&self, content_warning: Option<&str>) -> bool {
+        content_warning.is_some() && self.content_warnings == ContentWarnings::Ask
+    }
+
+    /// Save once no more changes were made for a moment, for sliders and text inputs
+    pub fn save_later(&mut self) {
+        self.dirty = Some(Instant::now());
+    }
+
+    /// Save changes from [Settings::save_later] which settled, called every frame
+    pub fn flush(&mut self, ctx: &Context) {
+        if let Some(t) = self.dirty {
+            if t.elapsed() >= SAVE_DELAY {
+                self.dirty = None;
+                self.save();
+            } else {
+                ctx.request_repaint_after(SAVE_DELAY - t.elapsed());
+            }
+        }
+    }
+
+    pub fn save(&self) {
+        info!("Saving settings: {}", self.path.display());
+        if let Some(dir) = self.path.parent() {
+            if let Err(e) = std::fs::create_dir_all(dir) {
+                warn!("Failed to create settings dir: {}", e);
+                return;
+            }
+        }
+        match serde_json::to_vec_pretty(self) {
+            Ok(json) => {
+                if let Err(e) = std::fs::write(&self.path, json) {
+                    warn!("Failed to save settings: {}", e);
+                }
+            }
+            Err(e) => warn!("Failed to serialize settings: {}", e),
+        }
+    }
+}
@@ -1,5 +1,8 @@
 use crate::note_util::NoteUtil;
+use crate::playback;
+use nostr::prelude::hex;
 use nostrdb::{NdbStrVariant, Note};
+use std::collections::HashSet;
 use std::fmt::{Display, Formatter};
 
 #[derive(Debug, Ord, PartialOrd, Eq, PartialEq, Clone)]
@@ -26,8 +29,12 @@ pub trait StreamInfo {
 
     fn host(&self) -> &[u8; 32];
 
+    /// Best playable streaming url, or the first one when none can be played here
     fn streaming(&self) -> Option<&str>;
 
+    /// All streaming urls in tag order
+    fn streaming_urls(&self) -> Vec<&str>;
+
     fn recording(&self) -> Option<&str>;
 
     /// Is the stream playable by this app
@@ -35,11 +42,37 @@ pub trait StreamInfo {
 
     fn starts(&self) -> u64;
 
+    /// Start time from the `starts` tag, [Self::starts] falls back to `created_at`
+    fn starts_tag(&self) -> Option<u64>;
+
     fn image(&self) -> Option<&str>;
 
+    /// Stream status from the `status` tag
     fn status(&self) -> StreamStatus;
 
+    /// Stream status at `now`, live streams without an update for `stale_after` seconds
+    /// are [StreamStatus::Ended]
+    fn status_at(&self, now: u64, stale_after: u64) -> StreamStatus;
+
     fn viewers(&self) -> Option<u32>;
+
+    /// Hashtags (`t` tags)
+    fn hashtags(&self) -> Vec<&str>;
+
+    /// Pubkeys in `p` tags, hosts and participants
+    fn participants(&self) -> Vec<[u8; 32]>;
+
+    /// Pubkeys in `p` tags with their role, `Participant` when not set
+    fn roles(&self) -> Vec<([u8; 32], &str)>;
+
+    /// Unix timestamp of the `ends` tag
+    fn ends(&self) -> Option<u64>;
+
+    /// Reason of the NIP-36 `content-warning` tag, empty when no reason is given
+    fn content_warning(&self) -> Option<&str>;
+
+    /// Author, host or a participant is one of these pubkeys
+    fn involves(&self, pubkeys: &HashSet<[u8; 32]>) -> bool;
 }
 
 impl StreamInfo for Note<'_> {
@@ -72,11 +105,20 @@ impl StreamInfo for Note<'_> {
     }
 
     fn streaming(&self) -> Option<&str> {
-        if let Some(s) = self.get_tag_value("streaming") {
-            s.variant().str()
-        } else {
-            None
-        }
+        let urls = self.streaming_urls();
+        playback::best_url(urls.iter().copied()).or(urls.first().copied())
+    }
+
+    fn streaming_urls(&self) -> Vec<&str> {
+        self.tags()
+            .iter()
+            .filter_map(|t| {
+                if t.count() < 2 || t.get(0)?.variant().str() != Some("streaming") {
+                    return None;
+                }
+                t.get(1)?.variant().str()
+            })
+            .collect()
     }
 
     fn recording(&self) -> Option<&str> {
@@ -92,21 +134,24 @@ impl StreamInfo for Note<'_> {
         if self.kind() == 30_313 {
             return true; // n94-stream can always be played
         }
-        if let Some(stream) = self.streaming() {
-            stream.contains(".m3u8")
+        let url = if self.status() == StreamStatus::Ended {
+            self.recording().or(self.streaming())
         } else {
-            false
-        }
+            self.streaming()
+        };
+        url.map_or(false, playback::can_play)
     }
 
     fn starts(&self) -> u64 {
-        if let Some(s) = self.get_tag_value("starts") {
-            s.variant().str().map_or(self.created_at(), |v| {
-                v.parse::<u64>().unwrap_or(self.created_at())
-            })
-        } else {
-            self.created_at()
-        }
+        self.starts_tag().unwrap_or(self.created_at())
+    }
+
+    fn starts_tag(&self) -> Option<u64> {
+        self.get_tag_value("starts")?
+            .variant()
+            .str()?
+            .parse::<u64>()
+            .ok()
     }
 
     fn image(&self) -> Option<&str> {
@@ -129,6 +174,15 @@ impl StreamInfo for Note<'_> {
         }
     }
 
+    fn status_at(&self, now: u64, stale_after: u64) -> StreamStatus {
+        match self.status() {
+            StreamStatus::Live if now.saturating_sub(self.created_at()) > stale_after => {
+                StreamStatus::Ended
+            }
+            s => s,
+        }
+    }
+
     fn viewers(&self) -> Option<u32> {
         if let Some(s) = self.get_tag_value("current_participants") {
             s.variant().str().map(|v| v.parse::<u32>().unwrap_or(0))
@@ -136,4 +190,71 @@ impl StreamInfo for Note<'_> {
             None
         }
     }
+
+    fn hashtags(&self) -> Vec<&str> {
+        self.tags()
+            .iter()
+            .filter_map(|t| {
+                if t.count() < 2 || t.get(0)?.variant().str() != Some("t") {
+                    return None;
+                }
+                t.get(1)?.variant().str()
+            })
+            .collect()
+    }
+
+    fn participants(&self) -> Vec<[u8; 32]> {
+        self.tags()
+            .iter()
+            .filter_map(|t| {
+                if t.count() < 2 || t.get(0)?.variant().str() != Some("p") {
+                    return None;
+                }
+                match t.get(1)?.variant() {
+                    NdbStrVariant::Id(i) => Some(*i),
+                    NdbStrVariant::Str(s) => hex::decode(s).ok()?.try_into().ok(),
+                }
+            })
+            .collect()
+    }
+
+    fn roles(&self) -> Vec<([u8; 32], &str)> {
+        self.tags()
+            .iter()
+            .filter_map(|t| {
+                if t.count() < 2 || t.get(0)?.variant().str() != Some("p") {
+                    return None;
+                }
+                let pubkey = match t.get(1)?.variant() {
+                    NdbStrVariant::Id(i) => *i,
+                    NdbStrVariant::Str(s) => hex::decode(s).ok()?.try_into().ok()?,
+                };
+                let role = t
+                    .get(3)
+                    .and_then(|r| r.variant().str())
+                    .filter(|r| !r.is_empty())
+                    .unwrap_or("Participant");
+                Some((pubkey, role))
+            })
+            .collect()
+    }
+
+    fn ends(&self) -> Option<u64> {
+        self.get_tag_value("ends")
+            .and_then(|e| e.variant().str())
+            .and_then(|e| e.parse().ok())
+    }
+
+    fn content_warning(&self) -> Option<&str> {
+        self.tags().iter().find_map(|t| {
+            if t.get(0)?.variant().str() != Some("content-warning") {
+                return None;
+            }
+            Some(t.get(1).and_then(|r| r.variant().str()).unwrap_or(""))
+        })
+    }
+
+    fn involves(&self, pubkeys: &HashSet<[u8; 32]>) -> bool {
+        pubkeys.contains(self.pubkey()) || self.participants().iter().any(|p| pubkeys.contains(p))
+    }
 }
@@ -1,6 +1,7 @@
 use crate::link::NostrLink;
 use crate::note_ref::NoteRef;
 use crate::route::RouteServices;
+use crate::stream_info::StreamInfo;
 use crate::sub::SubRef;
 use crate::widgets::chat_message::ChatMessage;
 use crate::widgets::chat_zap::ChatZap;
@@ -8,7 +9,7 @@ use crate::widgets::{sub_or_poll, NostrWidget};
 use crate::zap::Zap;
 use egui::{Frame, Margin, Response, ScrollArea, Ui};
 use itertools::Itertools;
-use nostrdb::{Filter, NoteKey};
+use nostrdb::{Filter, Ndb, NoteKey, Transaction};
 use std::collections::HashSet;
 
 pub struct Chat {
@@ -16,6 +17,11 @@ pub struct Chat {
     stream: NoteKey,
     events: HashSet<NoteRef>,
     sub: Option<SubRef>,
+
+    /// Unix timestamp of the replay position, events after this are hidden
+    replay_position: Option<u64>,
+    /// Recent chatters and the number of events they were computed from
+    chatters: (Vec<[u8; 32]>, usize),
 }
 
 impl Chat {
@@ -25,7 +31,49 @@ impl Chat {
             stream,
             events: HashSet::new(),
             sub: None,
+            replay_position: None,
+            chatters: (vec![], 0),
+        }
+    }
+
+    /// Only show events which happened before this point in time (replay mode)
+    pub fn set_replay_position(&mut self, pos: Option<u64>) {
+        self.replay_position = pos;
+    }
+
+    /// Time of the oldest loaded chat message or zap
+    pub fn first_event_at(&self) -> Option<u64> {
+        self.events.iter().map(|e| e.created_at).min()
+    }
+
+    /// Stream event this chat belongs to
+    pub fn stream(&self) -> NoteKey {
+        self.stream
+    }
+
+    /// All loaded chat messages and zaps
+    pub fn events(&self) -> impl Iterator<Item = &NoteRef> {
+        self.events.iter()
+    }
+
+    /// Authors of the most recent chat messages, newest first.
+    ///
+    /// Only recomputed when new events were loaded.
+    pub fn recent_chatters(&mut self, ndb: &Ndb, tx: &Transaction, limit: usize) -> &[[u8; 32]] {
+        if self.chatters.1 != self.events.len() {
+            let chatters = self
+                .events
+                .iter()
+                .sorted_by(|a, b| b.created_at.cmp(&a.created_at))
+                .filter_map(|e| ndb.get_note_by_key(tx, e.key).ok())
+                .filter(|e| e.kind() == 1311)
+                .map(|e| *e.pubkey())
+                .unique()
+                .take(limit)
+                .collect();
+            self.chatters = (chatters, self.events.len());
         }
+        &self.chatters.0
     }
 
     pub fn get_filter(&self) -> Filter {
@@ -55,13 +103,16 @@ impl NostrWidget for Chat {
                             for ev in self
                                 .events
                                 .iter()
+                                .filter(|e| {
+                                    self.replay_position.map_or(true, |p| e.created_at <= p)
+                                })
                                 .sorted_by(|a, b| a.created_at.cmp(&b.created_at))
                             {
                                 if let Ok(ev) =
                                     services.ctx.ndb.get_note_by_key(services.tx, ev.key)
                                 {
                                     match ev.kind() {
-                                        1311 => {
+                                        1311 if !services.settings.hides(ev.content_warning()) => {
                                             let profile = services.profile(ev.pubkey());
                                             ChatMessage::new(&stream, &ev, &profile)
                                                 .render(ui, services);
@@ -1,17 +1,22 @@
 use crate::link::NostrLink;
+use crate::nip05::Nip05Badge;
+use crate::outbox::PublishState;
 use crate::route::{RouteServices, RouteType};
 use crate::stream_info::StreamInfo;
 use crate::theme::{NEUTRAL_500, PRIMARY};
 use crate::widgets::Avatar;
 use eframe::epaint::text::TextWrapMode;
 use egui::text::LayoutJob;
-use egui::{Align, Color32, Label, Response, TextFormat, Ui};
+use egui::{
+    Align, Color32, CursorIcon, FontId, Id, Label, Response, RichText, Sense, TextFormat, Ui,
+};
 use nostrdb::{NdbProfile, Note};
 
 pub struct ChatMessage<'a> {
     stream: &'a Note<'a>,
     ev: &'a Note<'a>,
     profile: &'a Option<NdbProfile<'a>>,
+    font_size: Option<f32>,
 }
 
 impl<'a> ChatMessage<'a> {
@@ -24,11 +29,26 @@ impl<'a> ChatMessage<'a> {
             stream,
             ev,
             profile,
+            font_size: None,
         }
     }
 
+    pub fn font_size(mut self, size: f32) -> Self {
+        self.font_size = Some(size);
+        self
+    }
+
     pub fn render(self, ui: &mut Ui, services: &mut RouteServices) -> Response {
+        let publish_state = services.publish_state(self.ev.id());
+        let badge = services.nip05_badge(self.ev.pubkey(), self.profile);
+        let content_warning = self.ev.content_warning();
+        let shown_id = Id::new(("cw-shown", self.ev.id()));
+        let gated = services.settings.asks(content_warning)
+            && !ui.ctx().data(|d| d.get_temp(shown_id).unwrap_or(false));
         ui.horizontal_wrapped(|ui| {
+            if publish_state == Some(PublishState::Pending) {
+                ui.set_opacity(0.5);
+            }
             let mut job = LayoutJob::default();
             // TODO: avoid this somehow
             job.wrap.break_anywhere = true;
@@ -43,14 +63,41 @@ impl<'a> ChatMessage<'a> {
             let mut format = TextFormat::default();
             format.line_height = Some(24.0);
             format.valign = Align::Center;
+            if let Some(size) = self.font_size {
+                format.font_id = FontId::proportional(size);
+                format.line_height = Some(size * 1.5);
+            }
 
             format.color = name_color;
             job.append(name, 0.0, format.clone());
-            format.color = Color32::WHITE;
-            job.append(self.ev.content(), 5.0, format.clone());
+            match &badge {
+                Nip05Badge::Verified(domain) => {
+                    format.color = PRIMARY;
+                    job.append("✔", 4.0, format.clone());
+                    format.color = NEUTRAL_500;
+                    job.append(domain, 2.0, format.clone());
+                }
+                Nip05Badge::Lookalike => {
+                    format.color = Color32::RED;
+                    job.append("⚠", 4.0, format.clone());
+                }
+                Nip05Badge::None => {}
+            }
+            if gated {
+                format.color = NEUTRAL_500;
+                format.italics = true;
+                let text = match content_warning {
+                    Some(reason) if !reason.is_empty() => format!("⚠ {}, click to show", reason),
+                    _ => "⚠ Content warning, click to show".to_string(),
+                };
+                job.append(&text, 5.0, format.clone());
+            } else {
+                format.color = Color32::WHITE;
+                job.append(self.ev.content(), 5.0, format.clone());
+            }
 
             if Avatar::from_profile(self.profile)
-                .size(24.)
+                .size(self.font_size.map_or(24., |s| s * 1.5))
                 .render(ui, services.ctx.img_cache)
                 .clicked()
             {
@@ -58,7 +105,21 @@ impl<'a> ChatMessage<'a> {
                     link: NostrLink::profile(self.ev.pubkey()),
                 })
             }
-            ui.add(Label::new(job).wrap_mode(TextWrapMode::Wrap));
+            let label = Label::new(job).wrap_mode(TextWrapMode::Wrap);
+            if gated {
+                let rsp = ui
+                    .add(label.sense(Sense::click()))
+                    .on_hover_cursor(CursorIcon::PointingHand);
+                if rsp.clicked() {
+                    ui.ctx().data_mut(|d| d.insert_temp(shown_id, true));
+                }
+            } else {
+                ui.add(label);
+            }
+            if let Some(PublishState::Failed(reason)) = &publish_state {
+                ui.label(RichText::new("⚠").color(Color32::RED))
+                    .on_hover_text(format!("Failed to send: {}", reason));
+            }
 
             // consume reset of space
             ui.add_space(ui.available_size_before_wrap().x);
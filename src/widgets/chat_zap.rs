@@ -4,18 +4,28 @@ use crate::zap::{format_sats, Zap};
 use eframe::emath::Align;
 use eframe::epaint::text::{LayoutJob, TextFormat, TextWrapMode};
 use eframe::epaint::Color32;
-use egui::{Frame, Label, Response, Stroke, Ui};
+use egui::{FontId, Frame, Label, Response, Stroke, Ui};
 use nostrdb::NdbProfile;
 use notedeck::ImageCache;
 
 pub struct ChatZap<'a> {
     zap: &'a Zap<'a>,
     profile: &'a Option<NdbProfile<'a>>,
+    font_size: Option<f32>,
 }
 
 impl<'a> ChatZap<'a> {
     pub fn new(zap: &'a Zap, profile: &'a Option<NdbProfile<'a>>) -> Self {
-        Self { zap, profile }
+        Self {
+            zap,
+            profile,
+            font_size: None,
+        }
+    }
+
+    pub fn font_size(mut self, size: f32) -> Self {
+        self.font_size = Some(size);
+        self
     }
 
     pub fn render(self, ui: &mut Ui, img_cache: &mut ImageCache) -> Response {
@@ -36,6 +46,10 @@ impl<'a> ChatZap<'a> {
                     let mut format = TextFormat::default();
                     format.line_height = Some(24.0);
                     format.valign = Align::Center;
+                    if let Some(size) = self.font_size {
+                        format.font_id = FontId::proportional(size);
+                        format.line_height = Some(size * 1.5);
+                    }
 
                     format.color = ZAP;
                     job.append(name, 0.0, format.clone());
@@ -55,7 +69,7 @@ impl<'a> ChatZap<'a> {
                     }
 
                     Avatar::from_profile(self.profile)
-                        .size(24.)
+                        .size(self.font_size.map_or(24., |s| s * 1.5))
                         .render(ui, img_cache);
                     ui.add(Label::new(job).wrap_mode(TextWrapMode::Wrap));
 
@@ -0,0 +1,326 @@
+use crate::link::NostrLink;
+use crate::note_ref::NoteRef;
+use crate::note_util::NoteUtil;
+use crate::route::RouteServices;
+use crate::services::blossom;
+use crate::services::download::{save_segments, DownloadState};
+use crate::sub::SubRef;
+use crate::theme::{FONT_SIZE, MARGIN_DEFAULT, NEUTRAL_500, NEUTRAL_800, ROUNDING_DEFAULT};
+use crate::widgets::{sub_or_poll, Button, NativeTextInput, NostrWidget, Username};
+use egui::{Align, Color32, Frame, Layout, OpenUrl, ProgressBar, Response, RichText, Stroke, Ui};
+use itertools::Itertools;
+use log::{info, warn};
+use nostrdb::{Filter, Note};
+use std::collections::HashSet;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Longest clip which can be exported
+const MAX_CLIP_SECS: f32 = 120.0;
+
+#[derive(Debug, Clone, PartialEq)]
+enum ClipState {
+    /// Fraction of the segments downloaded
+    Exporting(f32),
+    Uploading,
+    /// Uploaded to the blossom server, ready to publish
+    Uploaded(String),
+    /// Saved locally only, no account to upload with
+    Saved(PathBuf),
+    Published,
+    Failed(String),
+}
+
+/// Clip tool and the list of clips (kind 1313) of a stream
+pub struct Clips {
+    link: NostrLink,
+    events: HashSet<NoteRef>,
+    sub: Option<SubRef>,
+
+    /// Playing the recording, used to find the player
+    replay: bool,
+    /// Clip tool is shown
+    open: bool,
+    start: Option<f32>,
+    end: Option<f32>,
+    title: String,
+    job: Option<Arc<Mutex<ClipState>>>,
+    /// Publishing the uploaded clip failed, it can be retried
+    publish_error: Option<String>,
+}
+
+impl Clips {
+    pub fn new(link: NostrLink) -> Self {
+        Self {
+            link,
+            events: HashSet::new(),
+            sub: None,
+            replay: false,
+            open: false,
+            start: None,
+            end: None,
+            title: String::new(),
+            job: None,
+            publish_error: None,
+        }
+    }
+
+    pub fn set_replay(&mut self, replay: bool) {
+        self.replay = replay;
+    }
+
+    /// Show or hide the clip tool
+    pub fn toggle_tool(&mut self) {
+        self.open = !self.open;
+    }
+
+    fn get_filter(&self) -> Filter {
+        Filter::new()
+            .kinds([1_313])
+            .tags([self.link.to_tag_value()], 'a')
+            .build()
+    }
+
+    fn job_state(&self) -> Option<ClipState> {
+        self.job.as_ref().map(|j| j.lock().unwrap().clone())
+    }
+
+    /// Download the segments between the marks, save them as MP4 and upload.
+    ///
+    /// Segments are copied whole, so the clip starts and ends at the segment
+    /// boundaries around the marks.
+    fn export(&mut self, ui: &Ui, services: &mut RouteServices<'_, '_>, start: f32, end: f32) {
+        let (segments, init) = match services.players.get(&self.link, self.replay) {
+            Some(p) => p.clip_segments(start, end),
+            None => return,
+        };
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_secs();
+        let out = services.downloads.dir().join(format!("clip-{}.mp4", now));
+        let keys = services.current_account_keys();
+        let server = services.settings.blossom_server.clone();
+        let ctx = ui.ctx().clone();
+        let state = Arc::new(Mutex::new(ClipState::Exporting(0.0)));
+        let worker_state = state.clone();
+        std::thread::spawn(move || {
+            let set = |s: ClipState| {
+                *worker_state.lock().unwrap() = s;
+                ctx.request_repaint();
+            };
+            let result = (|| -> anyhow::Result<ClipState> {
+                save_segments(&segments, init.as_deref(), &out, &|d| {
+                    if let DownloadState::Downloading(p) = d {
+                        set(ClipState::Exporting(p));
+                    }
+                })?;
+                info!("Saved clip {}", out.display());
+                let keys = if let Some(k) = keys {
+                    k
+                } else {
+                    return Ok(ClipState::Saved(out.clone()));
+                };
+                set(ClipState::Uploading);
+                let blob = blossom::upload(&server, &keys, std::fs::read(&out)?, "video/mp4")?;
+                Ok(ClipState::Uploaded(blob.url))
+            })();
+            set(result.unwrap_or_else(|e| {
+                warn!("Clip export failed: {}", e);
+                ClipState::Failed(e.to_string())
+            }));
+        });
+        self.publish_error = None;
+        self.job = Some(state);
+    }
+
+    fn publish(&mut self, services: &mut RouteServices<'_, '_>, url: &str) {
+        let title = if self.title.is_empty() {
+            "Clip"
+        } else {
+            self.title.as_str()
+        };
+        match services.write_live_clip(&self.link, url, title) {
+            Ok(ev) => {
+                services.broadcast_event(ev);
+                self.publish_error = None;
+                self.job = Some(Arc::new(Mutex::new(ClipState::Published)));
+            }
+            // keep the uploaded url to retry
+            Err(e) => self.publish_error = Some(e.to_string()),
+        }
+    }
+
+    fn render_tool(&mut self, ui: &mut Ui, services: &mut RouteServices<'_, '_>) {
+        let position = services
+            .players
+            .get(&self.link, self.replay)
+            .and_then(|p| p.position());
+        Frame::none()
+            .stroke(Stroke::new(1.0, NEUTRAL_800))
+            .rounding(ROUNDING_DEFAULT)
+            .inner_margin(MARGIN_DEFAULT)
+            .show(ui, |ui| {
+                ui.spacing_mut().item_spacing.y = 8.;
+                ui.label(RichText::new("Create clip").size(18.));
+                if position.is_none() {
+                    ui.label(
+                        RichText::new("Clips can only be made from HLS streams").color(NEUTRAL_500),
+                    );
+                    return;
+                }
+                ui.horizontal(|ui| {
+                    if Button::new().text(ui, "Mark in").clicked() {
+                        self.start = position;
+                    }
+                    ui.label(self.start.map_or("--:--".to_string(), format_time));
+                    if Button::new().text(ui, "Mark out").clicked() {
+                        self.end = position;
+                    }
+                    ui.label(self.end.map_or("--:--".to_string(), format_time));
+                });
+                ui.add(NativeTextInput::new(&mut self.title).with_hint_text("Clip title"));
+
+                let range = match (self.start, self.end) {
+                    (Some(s), Some(e)) if e > s && e - s <= MAX_CLIP_SECS => Some((s, e)),
+                    (Some(s), Some(e)) if e - s > MAX_CLIP_SECS => {
+                        ui.label(
+                            RichText::new(format!(
+                                "Clips can be at most {} seconds",
+                                MAX_CLIP_SECS as u32
+                            ))
+                            .color(Color32::RED),
+                        );
+                        None
+                    }
+                    _ => None,
+                };
+                if let Some((s, e)) = range.and_then(|(s, e)| {
+                    services
+                        .players
+                        .get(&self.link, self.replay)
+                        .and_then(|p| p.clip_bounds(s, e))
+                }) {
+                    ui.label(
+                        RichText::new(format!(
+                            "Clip is cut at segment boundaries: {} - {}",
+                            format_time(s),
+                            format_time(e)
+                        ))
+                        .color(NEUTRAL_500),
+                    );
+                }
+                let state = self.job_state();
+                let busy = matches!(
+                    state,
+                    Some(ClipState::Exporting(_)) | Some(ClipState::Uploading)
+                );
+                if Button::new()
+                    .disabled(range.is_none() || busy)
+                    .text(ui, "Export")
+                    .clicked()
+                {
+                    if let (Some((s, e)), false) = (range, busy) {
+                        self.export(ui, services, s, e);
+                    }
+                }
+                match state {
+                    Some(ClipState::Exporting(p)) => {
+                        ui.add(ProgressBar::new(p).show_percentage());
+                    }
+                    Some(ClipState::Uploading) => {
+                        ui.label("Uploading..");
+                    }
+                    Some(ClipState::Uploaded(url)) => {
+                        if Button::new().text(ui, "Publish clip").clicked() {
+                            self.publish(services, &url);
+                        }
+                        if let Some(e) = &self.publish_error {
+                            ui.label(RichText::new(e).color(Color32::RED));
+                        }
+                    }
+                    Some(ClipState::Saved(path)) => {
+                        ui.label(format!(
+                            "Saved to {}, login to publish clips",
+                            path.display()
+                        ));
+                    }
+                    Some(ClipState::Published) => {
+                        ui.label("Clip published");
+                    }
+                    Some(ClipState::Failed(e)) => {
+                        ui.label(RichText::new(e).color(Color32::RED));
+                    }
+                    None => {}
+                }
+            });
+    }
+
+    fn render_clip(ui: &mut Ui, services: &mut RouteServices<'_, '_>, clip: &Note<'_>) {
+        let url = clip.get_tag_value("r").and_then(|r| r.variant().str());
+        let title = clip
+            .get_tag_value("title")
+            .and_then(|t| t.variant().str())
+            .unwrap_or(clip.content());
+        let profile = services.profile(clip.pubkey());
+        ui.horizontal(|ui| {
+            ui.vertical(|ui| {
+                ui.label(title);
+                ui.add(Username::new(&profile, FONT_SIZE));
+            });
+            if let Some(url) = url {
+                ui.with_layout(Layout::right_to_left(Align::Center), |ui| {
+                    if Button::new().text(ui, "Watch").clicked() {
+                        ui.ctx().open_url(OpenUrl::new_tab(url));
+                    }
+                });
+            }
+        });
+    }
+}
+
+impl NostrWidget for Clips {
+    fn render(&mut self, ui: &mut Ui, services: &mut RouteServices<'_, '_>) -> Response {
+        ui.vertical(|ui| {
+            if self.open {
+                self.render_tool(ui, services);
+            }
+            let clips: Vec<Note> = self
+                .events
+                .iter()
+                .sorted_by(|a, b| b.created_at.cmp(&a.created_at))
+                .filter_map(|e| services.ctx.ndb.get_note_by_key(services.tx, e.key).ok())
+                .collect();
+            if !clips.is_empty() {
+                ui.label(RichText::new("Clips").size(18.));
+                for c in clips.iter() {
+                    Self::render_clip(ui, services, c);
+                }
+            }
+        })
+        .response
+    }
+
+    fn update(&mut self, services: &mut RouteServices<'_, '_>) -> anyhow::Result<()> {
+        let filters = vec![self.get_filter()];
+        sub_or_poll(
+            services.ctx.ndb,
+            services.tx,
+            services.ctx.pool,
+            &mut self.events,
+            &mut self.sub,
+            filters,
+        )
+    }
+}
+
+/// Format seconds as h:mm:ss or m:ss
+fn format_time(secs: f32) -> String {
+    let secs = secs as u64;
+    if secs >= 3600 {
+        format!("{}:{:02}:{:02}", secs / 3600, (secs / 60) % 60, secs % 60)
+    } else {
+        format!("{}:{:02}", secs / 60, secs % 60)
+    }
+}
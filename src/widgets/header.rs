@@ -51,6 +51,18 @@ impl Header {
                             } else if Button::new().show(ui, |ui| ui.label("Login")).clicked() {
                                 services.navigate(RouteType::LoginPage);
                             }
+                            if Button::new().text(ui, "Library").clicked() {
+                                services.navigate(RouteType::LibraryPage);
+                            }
+                            if Button::new().text(ui, "Settings").clicked() {
+                                services.navigate(RouteType::SettingsPage);
+                            }
+                            if Button::new().text(ui, "Agenda").clicked() {
+                                services.navigate(RouteType::AgendaPage);
+                            }
+                            if Button::new().text(ui, "Search").clicked() {
+                                services.navigate(RouteType::SearchPage);
+                            }
                         });
                     },
                 )
@@ -0,0 +1,109 @@
+use crate::theme::{FONT_SIZE, NEUTRAL_800, NEUTRAL_900, ROUNDING_DEFAULT};
+use crate::widgets::StreamPlayer;
+use egui::{vec2, Align, Align2, Area, Context, Frame, Id, Label, Layout, RichText, Stroke, Ui};
+
+/// Width of the floating player, height is 16:9
+const MINI_PLAYER_WIDTH: f32 = 320.0;
+
+/// Width of the thumbnail in the audio only bar
+const AUDIO_THUMB_WIDTH: f32 = 64.0;
+
+pub enum MiniPlayerAction {
+    /// Go back to the stream page
+    Expand,
+    /// Switch between the floating player and the audio only bar
+    ToggleAudioOnly,
+    Close,
+}
+
+/// Keeps a stream playing in the corner of the window while browsing
+pub struct MiniPlayer<'a> {
+    player: &'a mut StreamPlayer,
+    title: &'a str,
+    audio_only: bool,
+}
+
+impl<'a> MiniPlayer<'a> {
+    pub fn new(player: &'a mut StreamPlayer, title: &'a str, audio_only: bool) -> Self {
+        Self {
+            player,
+            title,
+            audio_only,
+        }
+    }
+
+    pub fn show(self, ctx: &Context) -> Option<MiniPlayerAction> {
+        let mut action = None;
+        Area::new(Id::new("mini-player"))
+            .anchor(Align2::RIGHT_BOTTOM, vec2(-16.0, -16.0))
+            .show(ctx, |ui| {
+                Frame::none()
+                    .fill(NEUTRAL_900)
+                    .stroke(Stroke::new(1.0, NEUTRAL_800))
+                    .rounding(ROUNDING_DEFAULT)
+                    .inner_margin(6.0)
+                    .show(ui, |ui| {
+                        if self.audio_only {
+                            // the player still has to run, it only draws a placeholder in audio only mode
+                            ui.horizontal(|ui| {
+                                ui.add_sized(
+                                    vec2(AUDIO_THUMB_WIDTH, AUDIO_THUMB_WIDTH / 16.0 * 9.0),
+                                    &mut *self.player,
+                                );
+                                let title = ui.add_sized(
+                                    vec2(MINI_PLAYER_WIDTH - AUDIO_THUMB_WIDTH - 120.0, 24.0),
+                                    Label::new(RichText::new(self.title).size(FONT_SIZE))
+                                        .truncate(),
+                                );
+                                if self.player.decodes_video() {
+                                    title.on_hover_text(
+                                        "This stream has no audio only version, video is still downloaded",
+                                    );
+                                }
+                                ui.with_layout(Layout::right_to_left(Align::Center), |ui| {
+                                    Self::buttons(ui, true, &mut action)
+                                });
+                            });
+                        } else {
+                            ui.add_sized(
+                                vec2(MINI_PLAYER_WIDTH, MINI_PLAYER_WIDTH / 16.0 * 9.0),
+                                &mut *self.player,
+                            );
+                            ui.allocate_ui_with_layout(
+                                vec2(MINI_PLAYER_WIDTH, 24.0),
+                                Layout::left_to_right(Align::Center),
+                                |ui| {
+                                    ui.add(
+                                        Label::new(RichText::new(self.title).size(FONT_SIZE))
+                                            .truncate(),
+                                    );
+                                },
+                            );
+                            ui.allocate_ui_with_layout(
+                                vec2(MINI_PLAYER_WIDTH, 24.0),
+                                Layout::right_to_left(Align::Center),
+                                |ui| Self::buttons(ui, false, &mut action),
+                            );
+                        }
+                    });
+            });
+        action
+    }
+
+    fn buttons(ui: &mut Ui, audio_only: bool, action: &mut Option<MiniPlayerAction>) {
+        if ui.button("✖").on_hover_text("Close").clicked() {
+            *action = Some(MiniPlayerAction::Close);
+        }
+        if ui.button("⬈").on_hover_text("Open stream").clicked() {
+            *action = Some(MiniPlayerAction::Expand);
+        }
+        let hint = if audio_only {
+            "Show video"
+        } else {
+            "Audio only"
+        };
+        if ui.button("🎧").on_hover_text(hint).clicked() {
+            *action = Some(MiniPlayerAction::ToggleAudioOnly);
+        }
+    }
+}
@@ -3,10 +3,14 @@ mod button;
 mod chat;
 mod chat_message;
 mod chat_zap;
+mod clips;
 mod header;
+mod mini_player;
+mod nip05_mark;
 mod pill;
 mod placeholder_rect;
 mod profile;
+mod reminder;
 mod stream_list;
 mod stream_player;
 mod stream_tile;
@@ -19,6 +23,7 @@ mod zap;
 use crate::note_ref::NoteRef;
 use crate::route::RouteServices;
 use crate::sub::SubRef;
+use chrono::{DateTime, Local};
 use egui::{Response, Ui};
 use enostr::RelayPool;
 use nostrdb::{Filter, Ndb, Transaction};
@@ -33,6 +38,13 @@ pub trait NostrWidget {
     fn update(&mut self, services: &mut RouteServices<'_, '_>) -> anyhow::Result<()>;
 }
 
+/// Unix timestamp in the local timezone
+pub fn local_time(t: u64) -> DateTime<Local> {
+    DateTime::from_timestamp(t as i64, 0)
+        .unwrap_or_default()
+        .with_timezone(&Local)
+}
+
 /// On widget update call this to update NDB data
 pub fn sub_or_poll(
     ndb: &Ndb,
@@ -64,10 +76,16 @@ pub fn sub_or_poll(
 pub use self::avatar::Avatar;
 pub use self::button::Button;
 pub use self::chat::Chat;
+pub use self::chat_message::ChatMessage;
+pub use self::chat_zap::ChatZap;
+pub use self::clips::Clips;
 pub use self::header::Header;
+pub use self::mini_player::{MiniPlayer, MiniPlayerAction};
+pub use self::nip05_mark::Nip05Mark;
 pub use self::pill::Pill;
 pub use self::placeholder_rect::PlaceholderRect;
 pub use self::profile::Profile;
+pub use self::reminder::ReminderButtons;
 pub use self::stream_list::StreamList;
 pub use self::stream_player::StreamPlayer;
 pub use self::stream_title::StreamTitle;
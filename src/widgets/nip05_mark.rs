@@ -0,0 +1,34 @@
+use crate::nip05::Nip05Badge;
+use crate::theme::{NEUTRAL_500, PRIMARY};
+use egui::{Color32, Response, RichText, Sense, Ui, Vec2, Widget};
+
+/// Verification mark shown after a name
+pub struct Nip05Mark {
+    badge: Nip05Badge,
+    size: f32,
+}
+
+impl Nip05Mark {
+    pub fn new(badge: Nip05Badge, size: f32) -> Self {
+        Self { badge, size }
+    }
+}
+
+impl Widget for Nip05Mark {
+    fn ui(self, ui: &mut Ui) -> Response {
+        match self.badge {
+            Nip05Badge::Verified(domain) => ui
+                .horizontal(|ui| {
+                    ui.spacing_mut().item_spacing.x = 2.;
+                    ui.label(RichText::new("✔").size(self.size).color(PRIMARY));
+                    ui.label(RichText::new(domain).size(self.size).color(NEUTRAL_500));
+                })
+                .response
+                .on_hover_text("Verified NIP-05"),
+            Nip05Badge::Lookalike => ui
+                .label(RichText::new("⚠").size(self.size).color(Color32::RED))
+                .on_hover_text("Name looks like a verified user, this may be an impersonator"),
+            Nip05Badge::None => ui.allocate_response(Vec2::ZERO, Sense::hover()),
+        }
+    }
+}
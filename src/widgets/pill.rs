@@ -1,10 +1,11 @@
 use crate::theme::{FONT_SIZE, NEUTRAL_800};
 use eframe::epaint::Margin;
-use egui::{Color32, Frame, Response, RichText, Ui, Widget};
+use egui::{Color32, CursorIcon, Frame, Response, RichText, Sense, Ui, Widget};
 
 pub struct Pill {
     text: String,
     color: Color32,
+    sense: Sense,
 }
 
 impl Pill {
@@ -12,9 +13,16 @@ impl Pill {
         Self {
             text: String::from(text),
             color: NEUTRAL_800,
+            sense: Sense::hover(),
         }
     }
 
+    /// Make the pill clickable
+    pub fn clickable(mut self) -> Self {
+        self.sense = Sense::click();
+        self
+    }
+
     pub fn color(mut self, color: Color32) -> Self {
         self.color = color;
         self
@@ -23,7 +31,7 @@ impl Pill {
 
 impl Widget for Pill {
     fn ui(self, ui: &mut Ui) -> Response {
-        Frame::default()
+        let response = Frame::default()
             .inner_margin(Margin::symmetric(5.0, 3.0))
             .rounding(5.0)
             .fill(self.color)
@@ -31,5 +39,11 @@ impl Widget for Pill {
                 ui.label(RichText::new(&self.text).size(FONT_SIZE));
             })
             .response
+            .interact(self.sense);
+        if self.sense.click {
+            response.on_hover_cursor(CursorIcon::PointingHand)
+        } else {
+            response
+        }
     }
 }
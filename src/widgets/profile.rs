@@ -1,6 +1,6 @@
 use crate::route::RouteServices;
 use crate::theme::FONT_SIZE;
-use crate::widgets::{Avatar, Username};
+use crate::widgets::{Avatar, Nip05Mark, Username};
 use egui::{Response, Ui};
 use nostrdb::NdbProfile;
 
@@ -43,7 +43,10 @@ impl<'a> Profile<'a> {
             Avatar::from_profile(&profile)
                 .size(self.size)
                 .render(ui, services.ctx.img_cache);
-            ui.add(Username::new(&profile, FONT_SIZE))
+            let rsp = ui.add(Username::new(&profile, FONT_SIZE));
+            let badge = services.nip05_badge(self.pubkey, &profile);
+            ui.add(Nip05Mark::new(badge, FONT_SIZE));
+            rsp
         })
         .response
     }
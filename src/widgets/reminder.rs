@@ -0,0 +1,53 @@
+use crate::link::NostrLink;
+use crate::route::RouteServices;
+use crate::services::reminders;
+use crate::theme::{NEUTRAL_500, PRIMARY};
+use crate::widgets::Button;
+use egui::{Response, RichText, Ui};
+use log::{info, warn};
+use nostrdb::Note;
+
+/// Remind me toggle and calendar export of a planned stream
+pub struct ReminderButtons<'a> {
+    event: &'a Note<'a>,
+}
+
+impl<'a> ReminderButtons<'a> {
+    pub fn new(event: &'a Note<'a>) -> Self {
+        Self { event }
+    }
+
+    pub fn render(self, ui: &mut Ui, services: &mut RouteServices<'_, '_>) -> Response {
+        let link = NostrLink::from_note(self.event);
+        let key = format!("ics-export:{}", link.to_tag_value());
+        ui.horizontal(|ui| {
+            let set = services.reminders.is_set(&link);
+            let btn = if set {
+                Button::new()
+                    .with_color(PRIMARY)
+                    .text(ui, "🔔 Reminder set")
+            } else {
+                Button::new().text(ui, "🔔 Remind me")
+            };
+            if btn.clicked() {
+                services.reminders.toggle(self.event);
+            }
+            if Button::new().text(ui, "📅 Add to calendar").clicked() {
+                match reminders::export_ics(self.event) {
+                    Ok(path) => {
+                        info!("Saved calendar entry to {}", path.display());
+                        services.set(&key, format!("Saved to {}", path.display()));
+                    }
+                    Err(e) => {
+                        warn!("Failed to save calendar entry: {}", e);
+                        services.set(&key, e.to_string());
+                    }
+                }
+            }
+            if let Some(msg) = services.get::<String>(&key) {
+                ui.label(RichText::new(msg).color(NEUTRAL_500));
+            }
+        })
+        .response
+    }
+}
@@ -1,15 +1,25 @@
+use crate::link::NostrLink;
 use crate::note_view::NotesView;
 use crate::route::RouteServices;
+use crate::settings::StreamSort;
 use crate::stream_info::StreamInfo;
 use crate::theme::MARGIN_DEFAULT;
 use crate::widgets::stream_tile::StreamEvent;
 use egui::{vec2, Frame, Grid, Margin, Response, Ui, WidgetText};
 use itertools::Itertools;
+use nostrdb::Note;
+use std::cmp::Ordering;
+use std::collections::{HashMap, HashSet};
 
 pub struct StreamList<'a> {
     id: egui::Id,
     streams: NotesView<'a>,
     heading: Option<WidgetText>,
+    /// Streams involving these pubkeys are highlighted
+    highlight: Option<&'a HashSet<[u8; 32]>>,
+    sort: StreamSort,
+    /// Zapped msats by stream tag value, for [StreamSort::Zaps]
+    zaps: Option<&'a HashMap<String, u64>>,
 }
 
 impl<'a> StreamList<'a> {
@@ -22,9 +32,46 @@ impl<'a> StreamList<'a> {
             id,
             streams,
             heading: heading.map(Into::into),
+            highlight: None,
+            sort: StreamSort::Starts,
+            zaps: None,
         }
     }
 
+    pub fn sort(mut self, sort: StreamSort, zaps: Option<&'a HashMap<String, u64>>) -> Self {
+        self.sort = sort;
+        self.zaps = zaps;
+        self
+    }
+
+    fn compare(&self, a: &Note<'_>, b: &Note<'_>, services: &RouteServices<'_, '_>) -> Ordering {
+        let starts = a.starts().cmp(&b.starts()).reverse();
+        let order = match self.sort {
+            StreamSort::Starts => Ordering::Equal,
+            StreamSort::Viewers => a.viewers().cmp(&b.viewers()).reverse(),
+            StreamSort::Zaps => {
+                let zapped = |n: &Note<'_>| {
+                    self.zaps.map_or(0, |z| {
+                        z.get(&NostrLink::from_note(n).to_tag_value())
+                            .copied()
+                            .unwrap_or(0)
+                    })
+                };
+                zapped(a).cmp(&zapped(b)).reverse()
+            }
+        };
+        services
+            .stream_status(a)
+            .cmp(&services.stream_status(b))
+            .then(order)
+            .then(starts)
+    }
+
+    pub fn highlight(mut self, pubkeys: &'a HashSet<[u8; 32]>) -> Self {
+        self.highlight = Some(pubkeys);
+        self
+    }
+
     pub fn render(&mut self, ui: &mut Ui, services: &mut RouteServices<'_, '_>) -> Response {
         let cols = match ui.available_width() as u16 {
             720..1080 => 2,
@@ -48,13 +95,16 @@ impl<'a> StreamList<'a> {
                     .spacing(vec2(grid_padding, grid_padding))
                     .show(ui, |ui| {
                         let mut ctr = 0;
-                        for event in self.streams.iter().sorted_by(|a, b| {
-                            a.status()
-                                .cmp(&b.status())
-                                .then(a.starts().cmp(&b.starts()).reverse())
-                        }) {
+                        for event in self
+                            .streams
+                            .iter()
+                            .filter(|e| !services.settings.hides(e.content_warning()))
+                            .sorted_by(|a, b| self.compare(a, b, services))
+                        {
                             ui.allocate_ui(vec2(g_w, (g_w / 16.0) * 9.0), |ui| {
-                                StreamEvent::new(event).render(ui, services)
+                                StreamEvent::new(event)
+                                    .highlight(self.highlight.map_or(false, |h| event.involves(h)))
+                                    .render(ui, services)
                             });
                             ctr += 1;
                             if ctr % cols == 0 {
@@ -1,30 +1,835 @@
+use crate::hls::{
+    load_playlist, parse_audio, parse_master, resolve_url, AbrController, HlsSegment, HlsVariant,
+    LiveTracker,
+};
+use crate::theme::{NEUTRAL_500, NEUTRAL_900, PRIMARY};
 use crate::widgets::PlaceholderRect;
-use egui::{Context, Response, Ui, Widget};
-use egui_video::{Player, PlayerControls};
+use egui::{
+    Align, Color32, Context, Frame, Layout, Response, RichText, Slider, Ui, UiBuilder, Vec2,
+    ViewportCommand, Widget,
+};
+use egui_video::{Player, PlayerControls, PlayerState};
+use log::{info, warn};
+use m3u8_rs::Playlist;
+use poll_promise::Promise;
+use std::time::{Duration, Instant};
+
+/// How often to measure throughput in auto quality mode
+const PROBE_INTERVAL: Duration = Duration::from_secs(10);
+
+/// Min time between automatic quality switches
+const SWITCH_INTERVAL: Duration = Duration::from_secs(15);
+
+/// Number of bytes downloaded from a segment to measure throughput
+const PROBE_BYTES: usize = 512 * 1024;
+
+/// Playback is considered dead when the position doesn't move for this long
+const STALL_TIMEOUT: Duration = Duration::from_secs(15);
+
+/// Max delay between reconnect attempts
+const RETRY_MAX_SECS: u64 = 30;
+
+/// Hide the controls when the mouse hasn't moved for this many seconds
+const CONTROLS_HIDE_SECS: f32 = 3.0;
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum Quality {
+    Auto,
+    Variant(usize),
+}
+
+enum Probe {
+    Idle(Instant),
+    Playlist(Promise<ehttp::Result<ehttp::Response>>),
+    Segment(Instant, Promise<ehttp::Result<ehttp::Response>>),
+}
 
 pub struct StreamPlayer {
+    ctx: Context,
+    url: String,
     player: Option<Player>,
+
+    /// Request for the master playlist, player starts once loaded
+    master: Option<Promise<ehttp::Result<ehttp::Response>>>,
+    variants: Vec<HlsVariant>,
+    quality: Quality,
+    /// Variant which is currently playing
+    current: Option<usize>,
+    /// Position to seek to once a VOD variant switch has loaded
+    pending_seek: Option<f32>,
+
+    abr: AbrController,
+    probe: Probe,
+    last_switch: Instant,
+    /// Time since the player (re)started and the position at that time
+    clock: Option<(Instant, f32)>,
+    /// Wall clock time not covered by playback at the last check
+    stall: f32,
+
+    /// Live media playlist tracking for the DVR window
+    live: Option<LiveTracker>,
+    live_reload: Option<Promise<ehttp::Result<ehttp::Response>>>,
+    next_reload: Instant,
+    /// Playing from the DVR window this many seconds behind live
+    dvr: Option<f32>,
+    /// Distance to the live edge when playback started
+    latency_base: Option<f32>,
+    /// Position of the DVR slider while dragging
+    dvr_slider: Option<f32>,
+
+    /// Reason the last playback attempt failed
+    error: Option<String>,
+    /// Reconnect after failures, only while the stream is live
+    auto_reconnect: bool,
+    retry_at: Option<Instant>,
+    attempts: u32,
+    /// When the playback position last changed
+    progress: (Instant, f32),
+
+    volume: f32,
+    muted: bool,
+
+    /// Listen without video to save bandwidth
+    audio_only: bool,
+    /// Audio only rendition from the master playlist
+    audio_url: Option<String>,
 }
 
 impl StreamPlayer {
     pub fn new(ctx: &Context, url: &String) -> Self {
-        let mut p = Player::new(ctx, url);
+        let mut ret = Self {
+            ctx: ctx.clone(),
+            url: url.clone(),
+            player: None,
+            master: None,
+            variants: vec![],
+            quality: Quality::Auto,
+            current: None,
+            pending_seek: None,
+            abr: AbrController::new(),
+            probe: Probe::Idle(Instant::now() + PROBE_INTERVAL),
+            last_switch: Instant::now(),
+            clock: None,
+            stall: 0.0,
+            live: None,
+            live_reload: None,
+            next_reload: Instant::now(),
+            dvr: None,
+            latency_base: None,
+            dvr_slider: None,
+            error: None,
+            auto_reconnect: false,
+            retry_at: None,
+            attempts: 0,
+            progress: (Instant::now(), 0.0),
+            volume: 1.0,
+            muted: false,
+            audio_only: false,
+            audio_url: None,
+        };
+        ret.restart();
+        ret
+    }
+
+    pub fn volume(&self) -> (f32, bool) {
+        (self.volume, self.muted)
+    }
+
+    pub fn set_volume(&mut self, volume: f32, muted: bool) {
+        if self.volume == volume && self.muted == muted {
+            return;
+        }
+        self.volume = volume;
+        self.muted = muted;
+        self.apply_volume();
+    }
+
+    fn apply_volume(&mut self) {
+        let volume = if self.muted { 0.0 } else { self.volume };
+        if let Some(p) = self.player.as_mut() {
+            p.set_volume(volume);
+        }
+    }
+
+    pub fn audio_only(&self) -> bool {
+        self.audio_only
+    }
+
+    /// Play the audio only rendition, or the lowest quality when the stream has none.
+    ///
+    /// Video frames are never drawn in audio only mode, without an audio only rendition
+    /// the video is still downloaded and decoded, see [Self::decodes_video]. Playback is
+    /// driven by the UI, so it doesn't continue while the Android activity is paused.
+    pub fn set_audio_only(&mut self, audio_only: bool) {
+        if self.audio_only == audio_only {
+            return;
+        }
+        self.audio_only = audio_only;
+        // still loading, or a single media playlist which keeps playing without drawing
+        if self.master.is_some() || (self.variants.is_empty() && self.audio_url.is_none()) {
+            return;
+        }
+        self.current = None;
+        self.select_media();
+    }
+
+    /// Audio only mode plays a stream with video, which is decoded but not drawn
+    pub fn decodes_video(&self) -> bool {
+        self.audio_only && self.master.is_none() && self.audio_url.is_none()
+    }
+
+    /// Reconnect automatically when playback fails
+    pub fn set_auto_reconnect(&mut self, auto_reconnect: bool) {
+        self.auto_reconnect = auto_reconnect;
+    }
+
+    /// Load the stream from scratch
+    fn restart(&mut self) {
+        self.retry_at = None;
+        self.variants.clear();
+        self.audio_url = None;
+        self.current = None;
+        self.live = None;
+        self.live_reload = None;
+        self.dvr = None;
+        self.latency_base = None;
+        if self.url.contains(".m3u8") {
+            self.master = Some(load_playlist(&self.ctx, &self.url));
+        } else {
+            self.play(&self.url.clone());
+        }
+    }
+
+    /// Stop playback and schedule a reconnect with backoff
+    fn fail(&mut self, reason: String) {
+        warn!("Playback failed: {}", reason);
+        self.player = None;
+        self.error = Some(reason);
+        self.retry_at = if self.auto_reconnect {
+            let backoff = (1u64 << self.attempts.min(5)).min(RETRY_MAX_SECS);
+            self.attempts += 1;
+            Some(Instant::now() + Duration::from_secs(backoff))
+        } else {
+            None
+        };
+    }
+
+    /// Detect players which stopped making progress
+    fn check_health(&mut self) {
+        if let Some(at) = self.retry_at {
+            if Instant::now() >= at {
+                self.restart();
+            } else {
+                self.ctx.request_repaint_after(Duration::from_secs(1));
+            }
+            return;
+        }
+        let (pos, state) = match &self.player {
+            Some(p) => (p.elapsed(), p.state()),
+            None => return,
+        };
+        match state {
+            PlayerState::EndOfFile if self.auto_reconnect && self.dvr.is_none() => {
+                self.fail("Stream ended unexpectedly".to_string());
+            }
+            PlayerState::EndOfFile | PlayerState::Paused => {
+                self.progress = (Instant::now(), pos);
+            }
+            _ if pos != self.progress.1 => {
+                self.progress = (Instant::now(), pos);
+                self.attempts = 0;
+            }
+            _ if self.progress.0.elapsed() > STALL_TIMEOUT => {
+                self.fail(if pos == 0.0 {
+                    "Failed to open stream".to_string()
+                } else {
+                    "Stream stalled".to_string()
+                });
+            }
+            _ => {}
+        }
+    }
+
+    fn render_error(&mut self, ui: &mut Ui) {
+        let error = if let Some(e) = &self.error {
+            e.clone()
+        } else {
+            return;
+        };
+        Frame::none()
+            .fill(NEUTRAL_900.gamma_multiply(0.8))
+            .rounding(5.0)
+            .inner_margin(8.0)
+            .show(ui, |ui| {
+                ui.vertical_centered(|ui| {
+                    ui.label(RichText::new(error).color(Color32::RED));
+                    if let Some(at) = self.retry_at {
+                        let secs = at.saturating_duration_since(Instant::now()).as_secs();
+                        ui.label(format!("Reconnecting in {}s..", secs + 1));
+                    } else if self.player.is_none() && ui.button("Retry").clicked() {
+                        self.attempts = 0;
+                        self.restart();
+                    }
+                });
+            });
+    }
+
+    /// Current playback position in seconds
+    pub fn elapsed(&self) -> f32 {
+        self.player.as_ref().map_or(0.0, |p| p.elapsed())
+    }
+
+    pub fn quality(&self) -> Quality {
+        self.quality
+    }
+
+    pub fn set_quality(&mut self, quality: Quality) {
+        if self.quality == quality {
+            return;
+        }
+        self.quality = quality;
+        if self.audio_only {
+            return;
+        }
+        let idx = match quality {
+            Quality::Auto => self.abr.pick(&self.variants, self.current, false),
+            Quality::Variant(i) => i,
+        };
+        self.switch_variant(idx);
+    }
+
+    fn fetch(ctx: &Context, req: ehttp::Request) -> Promise<ehttp::Result<ehttp::Response>> {
+        let (sender, promise) = Promise::new();
+        let ctx = ctx.clone();
+        ehttp::fetch(req, move |rsp| {
+            sender.send(rsp);
+            ctx.request_repaint();
+        });
+        promise
+    }
+
+    fn play(&mut self, url: &str) {
+        info!("Playing {}", url);
+        let mut p = Player::new(&self.ctx, &url.to_string());
         #[cfg(debug_assertions)]
         p.set_debug(true);
 
         p.start();
-        Self { player: Some(p) }
+        self.player = Some(p);
+        self.apply_volume();
+        self.clock = None;
+        self.stall = 0.0;
+        self.error = None;
+        self.progress = (Instant::now(), 0.0);
+    }
+
+    fn switch_variant(&mut self, idx: usize) {
+        if self.current == Some(idx) {
+            return;
+        }
+        let url = if let Some(v) = self.variants.get(idx) {
+            v.url.clone()
+        } else {
+            return;
+        };
+        self.current = Some(idx);
+        self.last_switch = Instant::now();
+        self.play_media(&url);
+    }
+
+    /// Switch to another media playlist of the same stream
+    fn play_media(&mut self, url: &str) {
+        // keep position when switching on a recording
+        if let Some(p) = &self.player {
+            if p.duration() > 0.0 {
+                self.pending_seek = Some(p.elapsed());
+            }
+        }
+        self.live = None;
+        self.dvr = None;
+        self.latency_base = None;
+        self.play(url);
+    }
+
+    /// Pick what to play from the master playlist
+    fn select_media(&mut self) {
+        if self.audio_only {
+            if let Some(url) = self.audio_url.clone() {
+                self.current = None;
+                self.play_media(&url);
+                return;
+            }
+        }
+        if self.variants.is_empty() {
+            self.play(&self.url.clone());
+            return;
+        }
+        let idx = match self.quality {
+            _ if self.audio_only => self.variants.len() - 1,
+            Quality::Variant(i) if i < self.variants.len() => i,
+            _ => self.abr.pick(&self.variants, self.current, false),
+        };
+        self.switch_variant(idx);
+    }
+
+    /// The media playlist which is playing
+    fn media_url(&self) -> &str {
+        if let (true, Some(url)) = (self.audio_only, &self.audio_url) {
+            return url;
+        }
+        self.current
+            .and_then(|c| self.variants.get(c))
+            .map_or(&self.url, |v| &v.url)
+    }
+
+    /// Reload the live media playlist to follow the live edge
+    fn poll_live(&mut self) {
+        if self.master.is_some() || self.player.is_none() || !self.media_url().contains(".m3u8") {
+            return;
+        }
+        if self.live.is_none() {
+            self.live = Some(LiveTracker::new(self.media_url()));
+        }
+        let tracker = self.live.as_mut().unwrap();
+        if tracker.ended() {
+            return;
+        }
+        if let Some(p) = self.live_reload.take() {
+            match p.try_take() {
+                Ok(Ok(r)) if r.ok => {
+                    if let Err(e) = tracker.update(&r.bytes) {
+                        warn!("{}", e);
+                    }
+                    if self.latency_base.is_none() && self.dvr.is_none() {
+                        self.latency_base = Some(tracker.start_latency());
+                    }
+                    self.next_reload = Instant::now() + tracker.reload_interval();
+                }
+                Ok(Ok(r)) => {
+                    self.fail(format!("Stream went offline ({})", r.status));
+                    return;
+                }
+                Ok(Err(e)) => {
+                    warn!("Failed to reload playlist: {}", e);
+                    self.next_reload = Instant::now() + tracker.reload_interval();
+                }
+                Err(p) => self.live_reload = Some(p),
+            }
+        } else if Instant::now() >= self.next_reload {
+            self.live_reload = Some(load_playlist(&self.ctx, tracker.url()));
+        }
+
+        // DVR playback reached the end of the snapshot
+        if let (Some(_), Some(p)) = (self.dvr, &self.player) {
+            if p.duration() > 0.0 && p.elapsed() >= p.duration() - 0.5 {
+                self.go_live();
+            }
+        }
+    }
+
+    /// Live stream with a DVR window
+    fn is_live(&self) -> bool {
+        self.live
+            .as_ref()
+            .map_or(false, |l| !l.ended() && l.window() > 0.0)
+    }
+
+    /// Estimated seconds behind the live edge
+    pub fn latency(&self) -> Option<f32> {
+        let live = self.live.as_ref()?;
+        if live.ended() {
+            return None;
+        }
+        Some(self.latency_base? + self.stall.max(0.0) + live.edge_age())
+    }
+
+    /// Playback position in the timeline of the media playlist, used to mark clips
+    pub fn position(&self) -> Option<f32> {
+        let live = self.live.as_ref()?;
+        if live.ended() {
+            Some(self.elapsed())
+        } else {
+            Some((live.edge_time() - self.latency()?).max(0.0))
+        }
+    }
+
+    /// Segments covering a range of [Self::position], with the init segment of fMP4 streams
+    pub fn clip_segments(&self, start: f32, end: f32) -> (Vec<HlsSegment>, Option<String>) {
+        self.live.as_ref().map_or((vec![], None), |l| {
+            (
+                l.segments_between(start, end),
+                l.init().map(|i| i.to_string()),
+            )
+        })
+    }
+
+    /// Range of [Self::position] a clip really covers, it's cut at segment boundaries
+    pub fn clip_bounds(&self, start: f32, end: f32) -> Option<(f32, f32)> {
+        self.live.as_ref()?.bounds_between(start, end)
+    }
+
+    /// Play from the DVR window `behind` seconds from the live edge
+    pub fn seek_live(&mut self, behind: f32) {
+        let live = if let Some(l) = &self.live {
+            l
+        } else {
+            return;
+        };
+        let path = std::env::temp_dir().join(format!(
+            "zap-stream-dvr-{}.m3u8",
+            egui::Id::new(&self.url).value()
+        ));
+        if let Err(e) = live.write_dvr_playlist(&path, behind) {
+            warn!("Failed to write DVR playlist: {}", e);
+            return;
+        }
+        self.dvr = Some(behind);
+        self.latency_base = Some(behind);
+        self.play(&path.to_string_lossy());
+    }
+
+    /// Return to the live edge
+    pub fn go_live(&mut self) {
+        if self.dvr.take().is_some() {
+            self.latency_base = self.live.as_ref().map(|l| l.start_latency());
+            self.play(&self.media_url().to_string());
+        }
+    }
+
+    fn render_live_bar(&mut self, ui: &mut Ui) {
+        let window = self.live.as_ref().map_or(0.0, |l| l.window());
+        let latency = self.latency().unwrap_or(0.0).min(window);
+        ui.horizontal(|ui| {
+            let at_live = self.dvr.is_none() && latency < 30.0;
+            let live_label =
+                RichText::new("LIVE").color(if at_live { PRIMARY } else { NEUTRAL_500 });
+            if ui
+                .button(live_label)
+                .on_hover_text("Jump to live")
+                .clicked()
+            {
+                self.go_live();
+            }
+            ui.label(format!("-{}s", latency as u32));
+
+            ui.spacing_mut().slider_width = ui.available_width();
+            let mut pos = self.dvr_slider.unwrap_or(window - latency);
+            let rsp = ui.add(Slider::new(&mut pos, 0.0..=window).show_value(false));
+            if rsp.dragged() {
+                self.dvr_slider = Some(pos);
+            }
+            if rsp.drag_stopped() || (rsp.changed() && !rsp.dragged()) {
+                self.dvr_slider = None;
+                self.seek_live(window - pos);
+            }
+        });
+    }
+
+    /// Play/pause, volume and fullscreen
+    fn render_controls(&mut self, ui: &mut Ui) {
+        let paused = self
+            .player
+            .as_ref()
+            .map_or(false, |p| matches!(p.state(), PlayerState::Paused));
+        ui.horizontal(|ui| {
+            let (icon, hint) = if paused {
+                ("⏵", "Play")
+            } else {
+                ("⏸", "Pause")
+            };
+            if ui.button(icon).on_hover_text(hint).clicked() {
+                if let Some(p) = self.player.as_mut() {
+                    if paused {
+                        p.start();
+                    } else {
+                        p.pause();
+                    }
+                }
+            }
+
+            let silent = self.muted || self.volume == 0.0;
+            let (icon, hint) = if silent {
+                ("🔇", "Unmute")
+            } else {
+                ("🔊", "Mute")
+            };
+            if ui.button(icon).on_hover_text(hint).clicked() {
+                self.set_volume(self.volume, !self.muted);
+            }
+            let mut volume = self.volume;
+            ui.spacing_mut().slider_width = 80.0;
+            if ui
+                .add(Slider::new(&mut volume, 0.0..=1.0).show_value(false))
+                .changed()
+            {
+                self.set_volume(volume, false);
+            }
+
+            ui.with_layout(Layout::right_to_left(Align::Center), |ui| {
+                let fullscreen = ui.ctx().input(|i| i.viewport().fullscreen.unwrap_or(false));
+                let hint = if fullscreen {
+                    "Exit fullscreen"
+                } else {
+                    "Fullscreen"
+                };
+                if ui.button("⛶").on_hover_text(hint).clicked() {
+                    ui.ctx()
+                        .send_viewport_cmd(ViewportCommand::Fullscreen(!fullscreen));
+                }
+                let hint = if self.audio_only {
+                    "Show video"
+                } else {
+                    "Audio only"
+                };
+                if ui.button("🎧").on_hover_text(hint).clicked() {
+                    self.set_audio_only(!self.audio_only);
+                }
+            });
+        });
+    }
+
+    /// Start playback once the master playlist is loaded
+    fn poll_master(&mut self) {
+        let rsp = match self.master.as_ref().map(|p| p.ready()) {
+            Some(Some(r)) => r,
+            _ => return,
+        };
+        let error = match rsp {
+            Ok(r) if r.ok => match parse_master(&self.url, &r.bytes) {
+                Ok(v) => {
+                    self.variants = v;
+                    self.audio_url = parse_audio(&self.url, &r.bytes);
+                    None
+                }
+                Err(e) => Some(e.to_string()),
+            },
+            Ok(r) => Some(format!("Stream not found ({})", r.status)),
+            Err(e) => Some(format!("Failed to load stream: {}", e)),
+        };
+        self.master = None;
+
+        if let Some(e) = error {
+            self.fail(e);
+        } else {
+            self.select_media();
+        }
+    }
+
+    /// Track how much wall clock time was not covered by playback (buffering)
+    fn update_stall(&mut self) -> bool {
+        let pos = self.elapsed();
+        let (start, start_pos) = match self.clock {
+            Some(c) => c,
+            None => {
+                if pos > 0.0 {
+                    self.clock = Some((Instant::now(), pos));
+                }
+                return false;
+            }
+        };
+        let stall = start.elapsed().as_secs_f32() - (pos - start_pos);
+        let starving = stall - self.stall > 1.0;
+        self.stall = stall;
+        starving
+    }
+
+    /// Measure throughput by downloading part of the latest segment
+    fn poll_probe(&mut self) {
+        let variant = match self.current.and_then(|c| self.variants.get(c)) {
+            Some(v) => v.clone(),
+            None => return,
+        };
+        self.probe = match std::mem::replace(&mut self.probe, Probe::Idle(Instant::now())) {
+            Probe::Idle(at) if Instant::now() >= at => {
+                Probe::Playlist(Self::fetch(&self.ctx, ehttp::Request::get(&variant.url)))
+            }
+            Probe::Playlist(p) => match p.try_take() {
+                Ok(Ok(r)) if r.ok => {
+                    let segment = match m3u8_rs::parse_playlist_res(&r.bytes) {
+                        Ok(Playlist::MediaPlaylist(m)) => m
+                            .segments
+                            .last()
+                            .and_then(|s| resolve_url(&variant.url, &s.uri).ok()),
+                        _ => None,
+                    };
+                    if let Some(s) = segment {
+                        let mut req = ehttp::Request::get(s);
+                        req.headers
+                            .insert("Range", format!("bytes=0-{}", PROBE_BYTES - 1));
+                        Probe::Segment(Instant::now(), Self::fetch(&self.ctx, req))
+                    } else {
+                        Probe::Idle(Instant::now() + PROBE_INTERVAL)
+                    }
+                }
+                Ok(_) => Probe::Idle(Instant::now() + PROBE_INTERVAL),
+                Err(p) => Probe::Playlist(p),
+            },
+            Probe::Segment(start, p) => match p.try_take() {
+                Ok(Ok(r)) if r.ok => {
+                    self.abr
+                        .add_sample(r.bytes.len(), start.elapsed().as_secs_f64());
+                    Probe::Idle(Instant::now() + PROBE_INTERVAL)
+                }
+                Ok(_) => Probe::Idle(Instant::now() + PROBE_INTERVAL),
+                Err(p) => Probe::Segment(start, p),
+            },
+            idle => idle,
+        };
+    }
+
+    /// Adaptive quality selection
+    fn update_abr(&mut self) {
+        let starving = self.update_stall();
+        if self.quality != Quality::Auto
+            || self.variants.is_empty()
+            || self.dvr.is_some()
+            || self.audio_only
+        {
+            return;
+        }
+        self.poll_probe();
+        if self.last_switch.elapsed() < SWITCH_INTERVAL {
+            return;
+        }
+        let idx = self.abr.pick(&self.variants, self.current, starving);
+        if Some(idx) != self.current {
+            info!(
+                "ABR switching to {} ({:?} bps)",
+                self.variants[idx].label(),
+                self.abr.estimate()
+            );
+            self.switch_variant(idx);
+        }
+    }
+
+    fn render_quality_menu(&mut self, ui: &mut Ui) {
+        let label = match self.quality {
+            Quality::Auto => format!(
+                "Auto{}",
+                self.current
+                    .and_then(|c| self.variants.get(c))
+                    .map_or(String::new(), |v| format!(" ({})", v.label()))
+            ),
+            Quality::Variant(i) => self.variants.get(i).map_or(String::new(), |v| v.label()),
+        };
+        let mut selected = self.quality;
+        Frame::none()
+            .fill(NEUTRAL_900)
+            .rounding(5.0)
+            .inner_margin(4.0)
+            .show(ui, |ui| {
+                ui.menu_button(RichText::new(label), |ui| {
+                    if ui
+                        .selectable_label(selected == Quality::Auto, "Auto")
+                        .clicked()
+                    {
+                        selected = Quality::Auto;
+                        ui.close_menu();
+                    }
+                    for (i, v) in self.variants.iter().enumerate() {
+                        if ui
+                            .selectable_label(selected == Quality::Variant(i), v.label())
+                            .clicked()
+                        {
+                            selected = Quality::Variant(i);
+                            ui.close_menu();
+                        }
+                    }
+                });
+            });
+        self.set_quality(selected);
     }
 }
 
 impl Widget for &mut StreamPlayer {
     fn ui(self, ui: &mut Ui) -> Response {
-        let size = ui.available_size();
+        self.poll_master();
+        self.update_abr();
+        self.poll_live();
+        self.check_health();
 
-        if let Some(p) = self.player.as_mut() {
-            ui.add_sized(size, p)
+        let size = ui.available_size();
+        let response = if let Some(p) = self.player.as_mut() {
+            if let Some(pos) = self.pending_seek {
+                let duration = p.duration();
+                if duration > 0.0 {
+                    p.seek(pos / duration);
+                    self.pending_seek = None;
+                }
+            }
+            if self.audio_only {
+                // the player only advances while its widget runs, keep it running unpainted
+                let rect = egui::Rect::from_min_size(ui.cursor().min, size);
+                ui.new_child(UiBuilder::new().max_rect(rect).invisible())
+                    .add_sized(size, p);
+                PlaceholderRect.ui(ui)
+            } else {
+                ui.add_sized(size, p)
+            }
         } else {
             PlaceholderRect.ui(ui)
+        };
+
+        if self.audio_only {
+            let mut overlay = ui.new_child(
+                UiBuilder::new()
+                    .max_rect(response.rect)
+                    .layout(Layout::centered_and_justified(egui::Direction::TopDown)),
+            );
+            let text = if self.decodes_video() {
+                "🎧 Audio only\nThis stream has no audio only version, video is still downloaded"
+            } else {
+                "🎧 Audio only"
+            };
+            overlay.label(RichText::new(text).size(20.0).color(NEUTRAL_500));
+        } else if self.variants.len() > 1 {
+            let mut overlay = ui.new_child(
+                UiBuilder::new()
+                    .max_rect(response.rect.shrink(8.0))
+                    .layout(Layout::right_to_left(Align::Min)),
+            );
+            self.render_quality_menu(&mut overlay);
+        }
+        let fullscreen = ui.ctx().input(|i| i.viewport().fullscreen.unwrap_or(false));
+        if fullscreen && ui.ctx().input(|i| i.key_pressed(egui::Key::Escape)) {
+            ui.ctx()
+                .send_viewport_cmd(ViewportCommand::Fullscreen(false));
+        }
+        let show_controls = self.dvr_slider.is_some()
+            || (ui.rect_contains_pointer(response.rect)
+                && ui.ctx().input(|i| i.pointer.time_since_last_movement()) < CONTROLS_HIDE_SECS);
+        if show_controls {
+            let bar_h = if self.is_live() { 68.0 } else { 36.0 };
+            let rect = response.rect.shrink(8.0);
+            let mut overlay = ui.new_child(UiBuilder::new().max_rect(egui::Rect::from_min_size(
+                rect.left_bottom() - Vec2::new(0.0, bar_h),
+                Vec2::new(rect.width(), bar_h),
+            )));
+            Frame::none()
+                .fill(NEUTRAL_900.gamma_multiply(0.8))
+                .rounding(5.0)
+                .inner_margin(4.0)
+                .show(&mut overlay, |ui| {
+                    if self.is_live() {
+                        self.render_live_bar(ui);
+                    }
+                    self.render_controls(ui);
+                });
+            // hide the controls again once the mouse stops moving
+            ui.ctx()
+                .request_repaint_after(Duration::from_secs_f32(CONTROLS_HIDE_SECS));
+        } else if fullscreen {
+            ui.ctx().set_cursor_icon(egui::CursorIcon::None);
+        }
+        if self.error.is_some() {
+            let mut overlay = ui.new_child(
+                UiBuilder::new()
+                    .max_rect(response.rect.shrink(8.0))
+                    .layout(Layout::top_down(Align::Center)),
+            );
+            overlay.add_space(response.rect.height() / 2.0 - 40.0);
+            self.render_error(&mut overlay);
         }
+        response
     }
 }
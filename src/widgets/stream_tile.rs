@@ -1,23 +1,34 @@
 use crate::link::NostrLink;
 use crate::route::{image_from_cache, RouteServices, RouteType};
 use crate::stream_info::{StreamInfo, StreamStatus};
-use crate::theme::{NEUTRAL_800, NEUTRAL_900, PRIMARY, ROUNDING_DEFAULT};
+use crate::theme::{FONT_SIZE_SM, NEUTRAL_800, NEUTRAL_900, PRIMARY, ROUNDING_DEFAULT};
 use crate::widgets::avatar::Avatar;
+use crate::widgets::{Nip05Mark, Username};
 use eframe::epaint::{Rounding, Vec2};
 use egui::epaint::RectShape;
 use egui::{
     vec2, Color32, CursorIcon, FontId, ImageSource, Label, Pos2, Rect, Response, RichText, Sense,
-    TextWrapMode, Ui,
+    Stroke, TextWrapMode, Ui,
 };
 use nostrdb::Note;
 
 pub struct StreamEvent<'a> {
     event: &'a Note<'a>,
+    /// Draw an outline around the cover, used for followed streams
+    highlight: bool,
 }
 
 impl<'a> StreamEvent<'a> {
     pub fn new(event: &'a Note<'a>) -> Self {
-        Self { event }
+        Self {
+            event,
+            highlight: false,
+        }
+    }
+
+    pub fn highlight(mut self, highlight: bool) -> Self {
+        self.highlight = highlight;
+        self
     }
 
     pub fn render(&mut self, ui: &mut Ui, services: &mut RouteServices<'_, '_>) -> Response {
@@ -26,18 +37,24 @@ impl<'a> StreamEvent<'a> {
 
             let host = self.event.host();
             let host_profile = services.profile(host);
+            let status = services.stream_status(self.event);
 
             let w = ui.available_width();
             let h = (w / 16.0) * 9.0;
 
             let (response, painter) = ui.allocate_painter(Vec2::new(w, h), Sense::click());
 
+            // sensitive covers are loaded tiny, scaling them up blurs them
+            let blur = services.settings.asks(self.event.content_warning());
+            let cover_size = if blur {
+                Vec2::new(16., 9.)
+            } else {
+                Vec2::new(w, h)
+            };
             let cover = if ui.is_rect_visible(response.rect) {
                 self.event
                     .image()
-                    .and_then(|p| {
-                        image_from_cache(services.ctx.img_cache, ui, p, Some(Vec2::new(w, h)))
-                    })
+                    .and_then(|p| image_from_cache(services.ctx.img_cache, ui, p, Some(cover_size)))
                     .map(|i| i.rounding(ROUNDING_DEFAULT))
             } else {
                 None
@@ -59,10 +76,28 @@ impl<'a> StreamEvent<'a> {
             } else {
                 painter.rect_filled(response.rect, ROUNDING_DEFAULT, NEUTRAL_800);
             }
+            if blur {
+                let cw_label = painter.layout_no_wrap(
+                    "⚠ CONTENT WARNING".to_string(),
+                    FontId::default(),
+                    Color32::WHITE,
+                );
+                let pad = Vec2::new(8., 5.);
+                let pos = response.rect.center() - cw_label.size() / 2.;
+                painter.rect_filled(
+                    Rect::from_min_size(pos - pad, cw_label.size() + pad * 2.),
+                    8.,
+                    NEUTRAL_900,
+                );
+                painter.galley(pos, cw_label, Color32::PLACEHOLDER);
+            }
+            if self.highlight {
+                painter.rect_stroke(response.rect, ROUNDING_DEFAULT, Stroke::new(2.0, PRIMARY));
+            }
 
             let overlay_label_pad = Vec2::new(5., 5.);
-            let live_label_text = self.event.status().to_string().to_uppercase();
-            let live_label_color = if self.event.status() == StreamStatus::Live {
+            let live_label_text = status.to_string().to_uppercase();
+            let live_label_color = if status == StreamStatus::Live {
                 PRIMARY
             } else {
                 NEUTRAL_900
@@ -103,6 +138,23 @@ impl<'a> StreamEvent<'a> {
                     Color32::PLACEHOLDER,
                 );
             }
+            if status != StreamStatus::Planned && !self.event.can_play() {
+                let external_label = painter.layout_no_wrap(
+                    "OPENS EXTERNALLY".to_string(),
+                    FontId::default(),
+                    Color32::WHITE,
+                );
+                let pos = Rect::from_two_pos(
+                    overlay_react.min,
+                    overlay_react.min + external_label.size() + (overlay_label_pad * 2.),
+                );
+                painter.rect_filled(pos, 8., NEUTRAL_900);
+                painter.galley(
+                    overlay_react.min + overlay_label_pad,
+                    external_label,
+                    Color32::PLACEHOLDER,
+                );
+            }
             let response = response.on_hover_and_drag_cursor(CursorIcon::PointingHand);
             if response.clicked() {
                 services.navigate(RouteType::EventPage {
@@ -114,10 +166,18 @@ impl<'a> StreamEvent<'a> {
                 Avatar::from_profile(&host_profile)
                     .size(40.)
                     .render(ui, services.ctx.img_cache);
-                let title = RichText::new(self.event.title().unwrap_or("Untitled"))
-                    .size(16.)
-                    .color(Color32::WHITE);
-                ui.add(Label::new(title).wrap_mode(TextWrapMode::Truncate));
+                ui.vertical(|ui| {
+                    ui.spacing_mut().item_spacing.y = 2.;
+                    let title = RichText::new(self.event.title().unwrap_or("Untitled"))
+                        .size(16.)
+                        .color(Color32::WHITE);
+                    ui.add(Label::new(title).wrap_mode(TextWrapMode::Truncate));
+                    ui.horizontal(|ui| {
+                        ui.add(Username::new(&host_profile, FONT_SIZE_SM));
+                        let badge = services.nip05_badge(host, &host_profile);
+                        ui.add(Nip05Mark::new(badge, FONT_SIZE_SM));
+                    });
+                });
             })
         })
         .response
@@ -1,12 +1,24 @@
+use crate::link::NostrLink;
 use crate::note_util::NoteUtil;
-use crate::route::RouteServices;
+use crate::route::{RouteServices, RouteType};
 use crate::stream_info::{StreamInfo, StreamStatus};
-use crate::theme::{MARGIN_DEFAULT, NEUTRAL_900, PRIMARY};
+use crate::theme::{MARGIN_DEFAULT, NEUTRAL_500, NEUTRAL_900, PRIMARY};
+use crate::widgets::local_time;
 use crate::widgets::zap::ZapButton;
+use crate::widgets::Button;
 use crate::widgets::Pill;
 use crate::widgets::Profile;
-use egui::{vec2, Color32, Frame, Label, Response, RichText, TextWrapMode, Ui};
+use crate::widgets::ReminderButtons;
+use egui::{
+    vec2, Color32, CursorIcon, Frame, Id, Label, Response, RichText, Sense, TextWrapMode, Ui,
+};
+use itertools::Itertools;
+use nostr::prelude::hex;
 use nostrdb::Note;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// How long the share button shows the link was copied
+const COPIED_DISPLAY: Duration = Duration::from_secs(3);
 
 pub struct StreamTitle<'a> {
     event: &'a Note<'a>,
@@ -17,6 +29,7 @@ impl<'a> StreamTitle<'a> {
         StreamTitle { event }
     }
     pub fn render(&mut self, ui: &mut Ui, services: &mut RouteServices<'_, '_>) -> Response {
+        let status = services.stream_status(self.event);
         Frame::none()
             .outer_margin(MARGIN_DEFAULT)
             .show(ui, |ui| {
@@ -32,22 +45,87 @@ impl<'a> StreamTitle<'a> {
                         .size(32.)
                         .render(ui, services);
                     ZapButton::event(self.event).render(ui, services);
+
+                    let host = hex::encode(self.event.host());
+                    let muted = services.settings.muted_streamers.contains(&host);
+                    let (icon, hint) = if muted {
+                        ("🔕", "Live notifications muted")
+                    } else {
+                        ("🔔", "Mute live notifications")
+                    };
+                    if Button::new().text(ui, icon).on_hover_text(hint).clicked() {
+                        if muted {
+                            services.settings.muted_streamers.remove(&host);
+                        } else {
+                            services.settings.muted_streamers.insert(host);
+                        }
+                        services.settings.save();
+                    }
+
+                    let link = NostrLink::from_note(self.event);
+                    let copied_id = Id::new(("share-copied", link.to_tag_value()));
+                    let now = ui.input(|i| i.time);
+                    let copied = match ui.ctx().data(|d| d.get_temp::<f64>(copied_id)) {
+                        Some(t) if now - t < COPIED_DISPLAY.as_secs_f64() => true,
+                        Some(_) => {
+                            ui.ctx().data_mut(|d| d.remove::<f64>(copied_id));
+                            false
+                        }
+                        None => false,
+                    };
+                    let text = if copied { "✔ Copied" } else { "🔗 Share" };
+                    if Button::new().text(ui, text).clicked() {
+                        ui.ctx().copy_text(link.to_string());
+                        ui.ctx().data_mut(|d| d.insert_temp(copied_id, now));
+                        ui.ctx().request_repaint_after(COPIED_DISPLAY);
+                    }
                 });
 
                 ui.horizontal(|ui| {
-                    let status = self.event.status().to_string().to_uppercase();
-                    let live_label_color = if self.event.status() == StreamStatus::Live {
+                    let live_label_color = if status == StreamStatus::Live {
                         PRIMARY
                     } else {
                         NEUTRAL_900
                     };
-                    ui.add(Pill::new(&status).color(live_label_color));
+                    ui.add(Pill::new(&status.to_string().to_uppercase()).color(live_label_color));
 
                     ui.add(Pill::new(&format!(
                         "{} viewers",
                         self.event.viewers().unwrap_or(0)
                     )));
+
+                    let (time, at) = self.time_info(&status);
+                    ui.add(Pill::new(&time))
+                        .on_hover_text(local_time(at).format("%c").to_string());
+
+                    if let Some(reason) = self.event.content_warning() {
+                        let text = if reason.is_empty() {
+                            "⚠ Content warning".to_string()
+                        } else {
+                            format!("⚠ {}", reason)
+                        };
+                        ui.add(Pill::new(&text).color(Color32::DARK_RED));
+                    }
                 });
+                if status == StreamStatus::Planned {
+                    ReminderButtons::new(self.event).render(ui, services);
+                }
+                let tags = self.event.hashtags();
+                if !tags.is_empty() {
+                    ui.horizontal_wrapped(|ui| {
+                        for tag in tags {
+                            if ui
+                                .add(Pill::new(&format!("#{}", tag)).clickable())
+                                .clicked()
+                            {
+                                services.navigate(RouteType::TagPage {
+                                    tag: tag.to_lowercase(),
+                                });
+                            }
+                        }
+                    });
+                }
+                self.render_participants(ui, services);
                 if let Some(summary) = self
                     .event
                     .get_tag_value("summary")
@@ -61,4 +139,86 @@ impl<'a> StreamTitle<'a> {
             })
             .response
     }
+
+    /// Start, duration or end of the stream depending on its status
+    fn time_info(&self, status: &StreamStatus) -> (String, u64) {
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_secs();
+        let starts = self.event.starts();
+        match status {
+            StreamStatus::Live => (
+                format!("Live for {}", format_duration(now.saturating_sub(starts))),
+                starts,
+            ),
+            StreamStatus::Planned if starts > now => (
+                format!("Starts in {}", format_duration(starts - now)),
+                starts,
+            ),
+            StreamStatus::Planned => ("Starting soon".to_string(), starts),
+            StreamStatus::Ended => match self.event.ends() {
+                Some(ends) => (
+                    format!("Ended {} ago", format_duration(now.saturating_sub(ends))),
+                    ends,
+                ),
+                None => (
+                    format!(
+                        "Started {} ago",
+                        format_duration(now.saturating_sub(starts))
+                    ),
+                    starts,
+                ),
+            },
+        }
+    }
+
+    /// Hosts, speakers and other participants from the `p` tags
+    fn render_participants(&self, ui: &mut Ui, services: &mut RouteServices<'_, '_>) {
+        let order = |role: &str| match role.to_lowercase().as_str() {
+            "host" => 0,
+            "speaker" => 1,
+            _ => 2,
+        };
+        let roles: Vec<([u8; 32], &str)> = self
+            .event
+            .roles()
+            .into_iter()
+            .unique_by(|(pk, _)| *pk)
+            .sorted_by_key(|(_, role)| order(role))
+            .collect();
+        if roles.is_empty() {
+            return;
+        }
+        ui.label(RichText::new("Participants").color(NEUTRAL_500));
+        ui.horizontal_wrapped(|ui| {
+            for (pk, role) in roles.iter() {
+                let rsp = ui
+                    .horizontal(|ui| {
+                        Profile::new(pk).size(24.).render(ui, services);
+                        ui.label(RichText::new(*role).color(NEUTRAL_500));
+                    })
+                    .response
+                    .interact(Sense::click())
+                    .on_hover_cursor(CursorIcon::PointingHand);
+                if rsp.clicked() {
+                    services.navigate(RouteType::ProfilePage {
+                        link: NostrLink::profile(pk),
+                    });
+                }
+            }
+        });
+    }
+}
+
+/// Short duration like `2h 15m`
+fn format_duration(secs: u64) -> String {
+    let (d, h, m) = (secs / 86_400, secs / 3_600 % 24, secs / 60 % 60);
+    if d > 0 {
+        format!("{}d {}h", d, h)
+    } else if h > 0 {
+        format!("{}h {}m", h, m)
+    } else {
+        format!("{}m", m.max(1))
+    }
 }
@@ -1,11 +1,13 @@
 use crate::theme::{MARGIN_DEFAULT, NEUTRAL_500, NEUTRAL_900, ROUNDING_DEFAULT};
-use egui::{Frame, Response, TextEdit, Ui, Widget};
+use egui::{Frame, KeyboardShortcut, Response, TextEdit, Ui, Widget};
 
 /// Wrap the [TextEdit] widget to handle native keyboard
 pub struct NativeTextInput<'a> {
     pub text: &'a mut String,
     hint_text: Option<&'a str>,
     frame: bool,
+    char_limit: Option<usize>,
+    return_key: Option<KeyboardShortcut>,
 }
 
 impl<'a> NativeTextInput<'a> {
@@ -14,6 +16,8 @@ impl<'a> NativeTextInput<'a> {
             text,
             hint_text: None,
             frame: false,
+            char_limit: None,
+            return_key: None,
         }
     }
 
@@ -26,6 +30,17 @@ impl<'a> NativeTextInput<'a> {
         self.frame = frame;
         self
     }
+
+    pub fn with_char_limit(mut self, limit: usize) -> Self {
+        self.char_limit = Some(limit);
+        self
+    }
+
+    /// Key combination which inserts a new line
+    pub fn with_return_key(mut self, return_key: KeyboardShortcut) -> Self {
+        self.return_key = Some(return_key);
+        self
+    }
 }
 
 impl Widget for NativeTextInput<'_> {
@@ -37,6 +52,12 @@ impl Widget for NativeTextInput<'_> {
         if let Some(hint_text) = self.hint_text {
             editor = editor.hint_text(egui::RichText::new(hint_text).color(NEUTRAL_500));
         }
+        if let Some(limit) = self.char_limit {
+            editor = editor.char_limit(limit);
+        }
+        if let Some(return_key) = self.return_key {
+            editor = editor.return_key(return_key);
+        }
 
         if self.frame {
             Frame::none()
@@ -1,14 +1,42 @@
 use crate::link::NostrLink;
-use crate::route::RouteServices;
-use crate::theme::{MARGIN_DEFAULT, NEUTRAL_900, ROUNDING_DEFAULT};
-use crate::widgets::NativeTextInput;
+use crate::route::{image_from_cache, RouteServices, RouteType};
+use crate::theme::{FONT_SIZE_SM, MARGIN_DEFAULT, NEUTRAL_500, NEUTRAL_900, ROUNDING_DEFAULT};
+use crate::widgets::{Avatar, Button, NativeTextInput};
 use eframe::emath::Align;
-use egui::{Frame, Image, Layout, Response, Sense, Ui, Widget};
+use egui::text::{CCursor, CCursorRange};
+use egui::text_edit::TextEditState;
+use egui::{
+    vec2, Color32, Context, Frame, Grid, Id, Image, Key, KeyboardShortcut, Layout, Modifiers,
+    Response, RichText, Sense, Ui, Widget,
+};
 use log::info;
+use nostr::nips::nip19::{FromBech32, ToBech32};
+use nostr::{Event, PublicKey, Tag};
+
+/// Max number of chars in a chat message
+const MAX_CHAT_LENGTH: usize = 512;
+
+const EMOJI: &[&str] = &[
+    "😂", "🤣", "😍", "🥰", "😎", "🤔", "😅", "😭", "😱", "🥳", "🙏", "👏", "👍", "👎", "🔥", "💯",
+    "⚡", "🧡", "💜", "🚀", "🎉", "👀", "🤙", "💪",
+];
 
 pub struct WriteChat {
     link: NostrLink,
     msg: String,
+    error: Option<String>,
+    /// Message input, to read and move its cursor
+    input_id: Option<Id>,
+    /// Char index of the cursor in [msg]
+    cursor: Option<usize>,
+
+    /// Users who can be mentioned with @
+    chatters: Vec<[u8; 32]>,
+    /// Custom emoji (shortcode, url) available for this stream
+    custom_emoji: Vec<(String, String)>,
+
+    /// Custom emoji used in [msg]
+    used_emoji: Vec<(String, String)>,
 }
 
 impl WriteChat {
@@ -16,35 +44,280 @@ impl WriteChat {
         Self {
             link,
             msg: String::new(),
+            error: None,
+            input_id: None,
+            cursor: None,
+            chatters: vec![],
+            custom_emoji: vec![],
+            used_emoji: vec![],
+        }
+    }
+
+    pub fn set_chatters(&mut self, chatters: &[[u8; 32]]) {
+        if self.chatters != chatters {
+            self.chatters = chatters.to_vec();
         }
     }
 
+    pub fn set_custom_emoji(&mut self, emoji: Vec<(String, String)>) {
+        self.custom_emoji = emoji;
+    }
+
     pub fn render(&mut self, ui: &mut Ui, services: &mut RouteServices<'_, '_>) -> Response {
-        let logo_bytes = include_bytes!("../resources/send-03.svg");
         Frame::none()
             .inner_margin(MARGIN_DEFAULT)
             .outer_margin(MARGIN_DEFAULT)
             .fill(NEUTRAL_900)
             .rounding(ROUNDING_DEFAULT)
             .show(ui, |ui| {
-                ui.with_layout(Layout::right_to_left(Align::Center), |ui| {
-                    if Image::from_bytes("send-03.svg", logo_bytes)
-                        .sense(Sense::click())
-                        .ui(ui)
+                if services.current_account_keys().is_none() {
+                    self.render_login_hint(ui, services);
+                } else {
+                    self.render_input(ui, services);
+                }
+            })
+            .response
+    }
+
+    fn render_login_hint(&mut self, ui: &mut Ui, services: &mut RouteServices<'_, '_>) {
+        ui.with_layout(Layout::right_to_left(Align::Center), |ui| {
+            if Button::new().show(ui, |ui| ui.label("Login")).clicked() {
+                services.navigate(RouteType::LoginPage);
+            }
+            ui.with_layout(Layout::left_to_right(Align::Center), |ui| {
+                ui.label(RichText::new("Login to chat").color(NEUTRAL_500));
+            });
+        });
+    }
+
+    fn render_input(&mut self, ui: &mut Ui, services: &mut RouteServices<'_, '_>) {
+        let logo_bytes = include_bytes!("../resources/send-03.svg");
+        if let Some(e) = &self.error {
+            ui.label(RichText::new(e).color(Color32::RED).size(FONT_SIZE_SM));
+        }
+        self.render_mentions(ui, services);
+
+        ui.with_layout(Layout::right_to_left(Align::Center), |ui| {
+            let send_clicked = Image::from_bytes("send-03.svg", logo_bytes)
+                .sense(Sense::click())
+                .ui(ui)
+                .clicked();
+
+            self.render_emoji_picker(ui, services);
+
+            let remaining = MAX_CHAT_LENGTH.saturating_sub(self.msg.chars().count());
+            if remaining < 50 {
+                ui.label(
+                    RichText::new(remaining.to_string())
+                        .color(NEUTRAL_500)
+                        .size(FONT_SIZE_SM),
+                );
+            }
+
+            let input = ui.add(
+                NativeTextInput::new(&mut self.msg)
+                    .with_hint_text("Message..")
+                    .with_char_limit(MAX_CHAT_LENGTH)
+                    .with_return_key(KeyboardShortcut::new(Modifiers::SHIFT, Key::Enter)),
+            );
+            let enter_pressed =
+                input.has_focus() && ui.input(|i| i.key_pressed(Key::Enter) && !i.modifiers.shift);
+            self.input_id = Some(input.id);
+            self.cursor = TextEditState::load(ui.ctx(), input.id)
+                .and_then(|s| s.cursor.char_range())
+                .map(|r| r.primary.index);
+
+            if send_clicked || enter_pressed {
+                self.send(services);
+                input.request_focus();
+            }
+        });
+    }
+
+    fn send(&mut self, services: &mut RouteServices<'_, '_>) {
+        let msg = self.msg.trim();
+        if msg.is_empty() {
+            return;
+        }
+
+        let event = (|| -> anyhow::Result<Event> {
+            let mut tags = vec![];
+            for pk in mentions(msg) {
+                tags.push(Tag::public_key(pk));
+            }
+            for (code, url) in self.used_emoji.iter() {
+                if msg.contains(&format!(":{}:", code)) {
+                    tags.push(Tag::parse(vec![
+                        "emoji".to_string(),
+                        code.to_string(),
+                        url.to_string(),
+                    ])?);
+                }
+            }
+            services.write_live_chat_msg(&self.link, msg, tags)
+        })();
+        match event {
+            Ok(ev) => {
+                info!("Sending: {:?}", ev);
+                services.broadcast_event(ev);
+                self.msg.clear();
+                self.used_emoji.clear();
+                self.error = None;
+            }
+            Err(e) => {
+                self.error = Some(format!("Failed to send: {}", e));
+            }
+        }
+    }
+
+    /// Byte offset of the cursor in [msg], the end when it's unknown
+    fn cursor_offset(&self) -> usize {
+        self.cursor
+            .and_then(|c| self.msg.char_indices().nth(c))
+            .map_or(self.msg.len(), |(i, _)| i)
+    }
+
+    /// The partial name being typed after an @ before the cursor
+    fn mention_query(&self) -> Option<&str> {
+        let (before, query) = self.msg[..self.cursor_offset()].rsplit_once('@')?;
+        if (before.is_empty() || before.ends_with(char::is_whitespace))
+            && !query.contains(char::is_whitespace)
+        {
+            Some(query)
+        } else {
+            None
+        }
+    }
+
+    fn render_mentions(&mut self, ui: &mut Ui, services: &mut RouteServices<'_, '_>) {
+        let query = if let Some(q) = self.mention_query() {
+            q.to_lowercase()
+        } else {
+            return;
+        };
+
+        let mut selected = None;
+        let mut shown = 0;
+        for pk in self.chatters.iter() {
+            let profile = services.profile(pk);
+            let name = profile
+                .and_then(|p| p.name())
+                .map(|n| n.to_string())
+                .unwrap_or_default();
+            if name.is_empty() || !name.to_lowercase().starts_with(&query) {
+                continue;
+            }
+            let row = ui.horizontal(|ui| {
+                Avatar::from_profile(&profile)
+                    .size(20.)
+                    .render(ui, services.ctx.img_cache);
+                ui.label(&name);
+                ui.add_space(ui.available_width());
+            });
+            if row.response.interact(Sense::click()).clicked() {
+                selected = Some(*pk);
+            }
+            shown += 1;
+            if shown == 5 {
+                break;
+            }
+        }
+
+        if let Some(pk) = selected {
+            self.insert_mention(ui.ctx(), &pk);
+        }
+    }
+
+    /// Replace the @ query before the cursor with the mention and move the cursor after it
+    fn insert_mention(&mut self, ctx: &Context, pubkey: &[u8; 32]) {
+        let npub = match PublicKey::from_slice(pubkey).map(|p| p.to_bech32()) {
+            Ok(Ok(npub)) => npub,
+            _ => return,
+        };
+        let end = self.cursor_offset();
+        let start = match self.msg[..end].rfind('@') {
+            Some(s) => s,
+            None => return,
+        };
+        let mention = format!("nostr:{} ", npub);
+        self.msg.replace_range(start..end, &mention);
+        let cursor = self.msg[..start].chars().count() + mention.chars().count();
+        self.cursor = Some(cursor);
+        if let Some(id) = self.input_id {
+            if let Some(mut state) = TextEditState::load(ctx, id) {
+                state
+                    .cursor
+                    .set_char_range(Some(CCursorRange::one(CCursor::new(cursor))));
+                state.store(ctx, id);
+            }
+        }
+    }
+
+    fn render_emoji_picker(&mut self, ui: &mut Ui, services: &mut RouteServices<'_, '_>) {
+        ui.menu_button(RichText::new("😀").size(18.), |ui| {
+            ui.set_max_width(260.);
+            Grid::new("chat-emoji-picker").show(ui, |ui| {
+                for (i, e) in EMOJI.iter().enumerate() {
+                    if ui
+                        .add(egui::Button::new(RichText::new(*e).size(18.)).frame(false))
                         .clicked()
-                        || self.msg.ends_with('\n')
                     {
-                        if let Some(ev) = services.write_live_chat_msg(&self.link, self.msg.trim())
-                        {
-                            info!("Sending: {:?}", ev);
-                            services.broadcast_event(ev);
+                        self.msg.push_str(e);
+                        ui.close_menu();
+                    }
+                    if (i + 1) % 8 == 0 {
+                        ui.end_row();
+                    }
+                }
+            });
+            if !self.custom_emoji.is_empty() {
+                ui.separator();
+                Grid::new("chat-custom-emoji-picker").show(ui, |ui| {
+                    for (i, (code, url)) in self.custom_emoji.iter().enumerate() {
+                        let resp = match image_from_cache(
+                            services.ctx.img_cache,
+                            ui,
+                            url,
+                            Some(vec2(24., 24.)),
+                        ) {
+                            Some(img) => img.sense(Sense::click()).ui(ui),
+                            None => ui.add(egui::Button::new(format!(":{}:", code)).frame(false)),
+                        }
+                        .on_hover_text(code);
+                        if resp.clicked() {
+                            self.msg.push_str(&format!(":{}:", code));
+                            if !self.used_emoji.iter().any(|(c, _)| c == code) {
+                                self.used_emoji.push((code.clone(), url.clone()));
+                            }
+                            ui.close_menu();
+                        }
+                        if (i + 1) % 8 == 0 {
+                            ui.end_row();
                         }
-                        self.msg.clear();
                     }
-
-                    ui.add(NativeTextInput::new(&mut self.msg).with_hint_text("Message.."));
                 });
-            })
-            .response
+            }
+        });
+    }
+}
+
+/// Pubkeys mentioned as `nostr:npub..` in a message, edits after picking a
+/// mention are taken into account
+fn mentions(msg: &str) -> Vec<PublicKey> {
+    let mut mentions = vec![];
+    for (_, rest) in msg
+        .match_indices("nostr:npub1")
+        .map(|(i, _)| msg.split_at(i + 6))
+    {
+        let npub: String = rest
+            .chars()
+            .take_while(|c| c.is_ascii_alphanumeric())
+            .collect();
+        if let Ok(pk) = PublicKey::from_bech32(&npub) {
+            if !mentions.contains(&pk) {
+                mentions.push(pk);
+            }
+        }
     }
+    mentions
 }